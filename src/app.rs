@@ -1,47 +1,144 @@
-use crate::utils::argminmax2::MinMax2;
 use crate::{utils, widgets};
 use std::error::{self};
+use std::sync::mpsc;
 
 #[derive(Debug, Clone, Copy)]
 pub enum AppMode {
     Xyz,
     MetaData,
+    /// Table of the clusters above a threshold next to the slices.
+    Clusters,
+    /// All opened images side by side with linked crosshairs.
+    Compare,
 }
 
-/// Application.
+/// How images are shown in [`AppMode::Compare`].
+#[derive(Debug, Clone, Copy)]
+pub enum CompareView {
+    /// One row per image.
+    Grid,
+    /// Alternates between the current and the next image.
+    Flicker,
+    /// Interleaves tiles of the current and the next image.
+    Checkerboard,
+}
+
+impl CompareView {
+    pub fn next(&self) -> Self {
+        match self {
+            CompareView::Grid => CompareView::Flicker,
+            CompareView::Flicker => CompareView::Checkerboard,
+            CompareView::Checkerboard => CompareView::Grid,
+        }
+    }
+}
+
+impl std::fmt::Display for CompareView {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Whole-image statistics listed in the metadata view.
 #[derive(Debug)]
-pub struct App {
-    /// Is the application running?
-    pub running: bool,
+pub enum Statistics {
+    /// Not requested yet.
+    Pending,
+    /// Computed on a background thread, see [`ImageState::update_statistics`].
+    Computing(mpsc::Receiver<widgets::key_value_list_widget::KeyValueList>),
+    /// Appended to the metadata.
+    Done,
+}
 
-    pub verbose: bool,
+/// Viewer state of a single opened image.
+#[derive(Debug)]
+pub struct ImageState {
     pub file_path: String,
+    /// Shown next to the file name, e.g. how a derived image was computed.
+    pub description: Option<String>,
     pub volume: utils::brain_volume::BrainVolume,
     pub image_cache: utils::slice_cache::SliceCache,
     pub intensity_range: (f64, f64),
+    /// Whether `intensity_range` is centred on zero.
+    pub symmetric: bool,
+    /// Intensity range restored when the symmetric range is switched off.
+    pub asymmetric_range: (f64, f64),
+    /// Lower and upper display threshold, voxels outside are transparent.
+    pub display_threshold: (Option<f64>, Option<f64>),
+    /// Number of NaN and infinite voxels of the first volume.
+    pub non_finite: usize,
     pub slice_position: Vec<f64>,
-    pub increment: f64,
-    pub mode: AppMode,
     pub color_map: utils::colors::ColorMap,
-    pub color_mode: utils::colors::ColorMode,
     pub metadata: widgets::key_value_list_widget::KeyValueList,
     pub metadata_index: usize,
+    pub statistics: Statistics,
+    /// Clusters above the peak threshold, found on first use.
+    pub clusters: Option<Vec<utils::clusters::Cluster>>,
+    /// Index of the cluster whose peak was jumped to last.
+    pub peak_index: Option<usize>,
+    /// Statistics of the last region of interest and the crosshair position it was centered at.
+    pub roi_stats: Option<(utils::roi::Roi, Vec<f64>, utils::roi::RoiStats)>,
 }
 
-impl App {
-    /// Constructs a new instance of [`App`].
+impl ImageState {
+    /// Loads an image and constructs a new instance of [`ImageState`].
     pub fn new(
         verbose: bool,
         file_path: &str,
-        color_mode: utils::colors::ColorMode,
     ) -> std::result::Result<Self, Box<dyn error::Error + Send + Sync>> {
         let start = std::time::Instant::now();
         let volume = utils::brain_volume::BrainVolume::from_nifti(file_path)?;
+        let duration = start.elapsed();
+
+        if verbose {
+            println!("Data '{}' loaded in: {:?}", file_path, duration);
+        }
+
+        Ok(Self::from_volume(file_path, volume))
+    }
+
+    /// Loads an image and compares it voxelwise with `other`.
+    pub fn new_difference(
+        verbose: bool,
+        file_path: &str,
+        other_path: &str,
+        other: &utils::brain_volume::BrainVolume,
+        op: utils::difference::DiffOp,
+    ) -> std::result::Result<Self, Box<dyn error::Error + Send + Sync>> {
+        let volume = utils::brain_volume::BrainVolume::from_nifti(file_path)?;
+
+        let start = std::time::Instant::now();
+        let difference = utils::difference::difference(&volume, other, op);
+        let duration = start.elapsed();
+
+        if verbose {
+            println!("Difference to '{}' computed in: {:?}", other_path, duration);
+        }
+
+        let mut image = Self::from_volume(file_path, difference.volume);
+        image.description = Some(format!("{} {}", op.symbol(), other_path));
+        image.intensity_range = op.intensity_range(difference.max_deviation);
+        image.symmetric = op == utils::difference::DiffOp::Difference;
+        image.color_map = match op {
+            utils::difference::DiffOp::Absolute => utils::colors::ColorMap::Inferno,
+            _ => utils::colors::ColorMap::RdBuR,
+        };
+        image
+            .metadata
+            .push(("Compared to".to_owned(), other_path.to_owned()));
+        image.metadata.extend(difference.summary);
+        Ok(image)
+    }
+
+    fn from_volume(file_path: &str, volume: utils::brain_volume::BrainVolume) -> Self {
         let intensity_range = volume.intensity_range;
         let middle_slice = volume.world_bounds.center().into_iter().collect();
-        let increment = volume.world_bounds.size().minmax2().0 / 32.0;
+        let non_finite = volume
+            .array_view_3d()
+            .iter()
+            .filter(|v| !v.is_finite())
+            .count();
         let metadata = utils::metadata::make_metadata_key_value_list(&volume.header);
-        let duration = start.elapsed();
 
         // Guess whether image is a mask or not
         let color_map = if utils::metadata::nifti_type_is_integer(
@@ -54,91 +151,996 @@ impl App {
             utils::colors::ColorMap::Inferno
         };
 
-        if verbose {
-            println!("Data loaded in: {:?}", duration);
+        Self {
+            file_path: file_path.to_string(),
+            description: None,
+            volume,
+            image_cache: utils::slice_cache::SliceCache::new(),
+            intensity_range,
+            symmetric: false,
+            asymmetric_range: intensity_range,
+            display_threshold: (None, None),
+            non_finite,
+            slice_position: middle_slice,
+            color_map,
+            metadata,
+            metadata_index: 0,
+            statistics: Statistics::Pending,
+            clusters: None,
+            peak_index: None,
+            roi_stats: None,
+        }
+    }
+
+    /// Moves the crosshair by `steps` voxel edge lengths along a world axis,
+    /// stopping at the world bounds.
+    pub fn step_slice(&mut self, axis: usize, steps: f64) {
+        let spacing = self.volume.world_spacing()[axis];
+        let bounds = self.volume.world_bounds;
+        self.slice_position[axis] = (self.slice_position[axis] + steps * spacing)
+            .clamp(bounds.min()[axis], bounds.max()[axis]);
+    }
+
+    pub fn toggle_color_map(&mut self) {
+        self.color_map = self.color_map.next();
+    }
+
+    /// Switches between the intensity range of the image and ±max|value|,
+    /// which puts zero in the middle of diverging color maps.
+    pub fn toggle_symmetric_range(&mut self) {
+        self.set_symmetric(!self.symmetric);
+    }
+
+    /// Sets the intensity range to ±max|value|, or back to the range before.
+    pub fn set_symmetric(&mut self, symmetric: bool) {
+        if symmetric == self.symmetric {
+            return;
+        }
+        self.symmetric = symmetric;
+        self.intensity_range = if symmetric {
+            self.asymmetric_range = self.intensity_range;
+            let (min, max) = self.volume.intensity_range;
+            let extent = min.abs().max(max.abs());
+            (-extent, extent)
+        } else {
+            self.asymmetric_range
+        };
+    }
+
+    /// Raises or lowers the lower (or `upper`) display threshold by 5 % of the intensity range.
+    ///
+    /// Thresholds are switched off when moved past the end of the intensity range,
+    /// and switched on by moving them back in.
+    pub fn change_display_threshold(&mut self, upper: bool, direction: f64) {
+        let (min, max) = self.intensity_range;
+        let step = (max - min) * DISPLAY_THRESHOLD_STEP;
+        let (lower_threshold, upper_threshold) = &mut self.display_threshold;
+        if upper {
+            let value = upper_threshold.unwrap_or(max) + direction * step;
+            *upper_threshold = (value < max - step / 2.)
+                .then(|| value.max(lower_threshold.unwrap_or(min)));
+        } else {
+            let value = lower_threshold.unwrap_or(min) + direction * step;
+            *lower_threshold = (value > min + step / 2.)
+                .then(|| value.min(upper_threshold.unwrap_or(max)));
         }
+    }
 
-        Ok(Self {
+    /// Crosshair position in the coordinates of the displayed slices.
+    pub fn position_in(&self, frame: &utils::sampling::SliceFrame) -> Vec<f64> {
+        match (frame.space, frame.transform()) {
+            (utils::sampling::SliceSpace::World, None) => self.slice_position.clone(),
+            (utils::sampling::SliceSpace::World, Some(transform)) => {
+                let inverse = utils::sampling::invert_affine_transform(&transform.view());
+                apply_transform(&inverse, &self.slice_position)
+            }
+            (utils::sampling::SliceSpace::Voxel, _) => {
+                self.volume.world_to_voxel(&self.slice_position).to_vec()
+            }
+        }
+    }
+
+    /// Moves the crosshair to a position in the coordinates of the displayed slices.
+    pub fn set_position_in(&mut self, position: &[f64], frame: &utils::sampling::SliceFrame) {
+        self.slice_position = match (frame.space, frame.transform()) {
+            (utils::sampling::SliceSpace::World, None) => position.to_vec(),
+            (utils::sampling::SliceSpace::World, Some(transform)) => {
+                apply_transform(&transform, position)
+            }
+            (utils::sampling::SliceSpace::Voxel, _) => {
+                self.volume.voxel_to_world(position).to_vec()
+            }
+        };
+    }
+
+    /// Moves the crosshair to the center of a neighbouring voxel along an array axis.
+    pub fn step_voxel(&mut self, axis: usize, step: f64) {
+        let mut voxel = self.volume.world_to_voxel(&self.slice_position);
+        let max = self.volume.local_bounds.max()[axis];
+        voxel[axis] = (voxel[axis].round() + step).clamp(0., max);
+        self.set_position_in(
+            &voxel,
+            &utils::sampling::SliceFrame::new(utils::sampling::SliceSpace::Voxel),
+        );
+    }
+
+    /// Region in the coordinates of the displayed slices shown at the given zoom level,
+    /// centered at the crosshair shifted by `pan`.
+    pub fn view_bounds(
+        &self,
+        zoom: f64,
+        pan: &[f64; 3],
+        frame: &utils::sampling::SliceFrame,
+    ) -> utils::sampling::Cube {
+        let position = self.position_in(frame);
+        let center: Vec<f64> = (0..3).map(|axis| position[axis] + pan[axis]).collect();
+        self.volume.bounds(frame.space).zoomed(&center, zoom)
+    }
+
+    /// Describes the crosshair position and the voxel below it.
+    pub fn crosshair_info(&self) -> widgets::key_value_list_widget::KeyValueList {
+        let units = utils::brain_volume::xyz_units_str(&self.volume.header);
+        let position = &self.slice_position;
+        let voxel = self.volume.voxel_index(position);
+        vec![
+            (
+                "Position".to_owned(),
+                format!(
+                    "{:.2}, {:.2}, {:.2} {}",
+                    position[2], position[1], position[0], units
+                ),
+            ),
+            (
+                "Voxel".to_owned(),
+                match voxel {
+                    Some([k, j, i]) => format!("{}, {}, {}", i, j, k),
+                    None => "outside".to_owned(),
+                },
+            ),
+            (
+                "Value".to_owned(),
+                match self.volume.value_at(position) {
+                    Some(value) => format!("{}", value),
+                    None => "-".to_owned(),
+                },
+            ),
+            (
+                "Range".to_owned(),
+                format!(
+                    "[{:.2}, {:.2}]",
+                    self.intensity_range.0, self.intensity_range.1
+                ),
+            ),
+            ("Color map".to_owned(), format!("{}", self.color_map)),
+            (
+                "Threshold".to_owned(),
+                match self.display_threshold {
+                    (None, None) => "-".to_owned(),
+                    (Some(lower), None) => format!("≥ {:.2}", lower),
+                    (None, Some(upper)) => format!("≤ {:.2}", upper),
+                    (Some(lower), Some(upper)) => format!("[{:.2}, {:.2}]", lower, upper),
+                },
+            ),
+        ]
+    }
+
+    /// Starts computing the whole-image statistics on a background thread when they
+    /// are first shown, and appends them to `metadata` once they are ready.
+    ///
+    /// Until then, the statistics header in `metadata` reads "computing…".
+    pub fn update_statistics(&mut self) {
+        match &self.statistics {
+            Statistics::Pending => {
+                let (sender, receiver) = mpsc::channel();
+                let arr = std::sync::Arc::clone(&self.volume.arr);
+                let integer = self
+                    .volume
+                    .header
+                    .data_type()
+                    .is_ok_and(utils::metadata::nifti_type_is_integer);
+                std::thread::spawn(move || {
+                    // the receiver is gone if the viewer was closed in the meantime
+                    let _ = sender.send(utils::metadata::make_statistics_key_value_list(&arr, integer));
+                });
+                self.metadata.push(("Statistics".to_owned(), "computing…".to_owned()));
+                self.statistics = Statistics::Computing(receiver);
+            }
+            Statistics::Computing(receiver) => {
+                let statistics = match receiver.try_recv() {
+                    Ok(statistics) => statistics,
+                    Err(mpsc::TryRecvError::Empty) => return,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        vec![("Error".to_owned(), "Computing the statistics failed".to_owned())]
+                    }
+                };
+                // replace the placeholder of the header row
+                if let Some(header) = self.metadata.last_mut() {
+                    header.1 = String::new();
+                }
+                self.metadata.extend(statistics);
+                self.statistics = Statistics::Done;
+            }
+            Statistics::Done => {}
+        }
+    }
+
+    pub fn increment_metadata_index(&mut self) {
+        if self.metadata_index < self.metadata.len() - 2 {
+            self.metadata_index += 1;
+        }
+    }
+
+    pub fn decrement_metadata_index(&mut self) {
+        if self.metadata_index > 0 {
+            self.metadata_index -= 1;
+        }
+    }
+}
+
+/// Parses three numbers separated by commas and/or whitespace, optionally in parentheses.
+fn parse_coordinates(text: &str) -> Option<[f64; 3]> {
+    let text = text.trim().trim_start_matches(['(', '[']).trim_end_matches([')', ']']);
+    let numbers: Vec<f64> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    numbers.try_into().ok()
+}
+
+/// Applies a 4 x 4 affine transform to a 3D position.
+fn apply_transform(transform: &ndarray::Array2<f64>, position: &[f64]) -> Vec<f64> {
+    let transformed = transform.dot(&ndarray::array![position[0], position[1], position[2], 1.]);
+    transformed.iter().take(3).copied().collect()
+}
+
+/// Radius of box and sphere regions of interest in world units (usually mm).
+pub const DEFAULT_ROI_RADIUS: f64 = 5.;
+
+/// Fraction of the intensity range the display thresholds change per step.
+const DISPLAY_THRESHOLD_STEP: f64 = 0.05;
+
+/// Fraction of the maximum the cluster threshold changes per step.
+const CLUSTER_THRESHOLD_STEP: f64 = 0.05;
+
+/// Factor applied to the zoom level per zoom step.
+const ZOOM_STEP: f64 = 1.5;
+const MAX_ZOOM: f64 = 32.;
+const MAX_MOSAIC_SLICES: usize = 64;
+
+/// Application.
+#[derive(Debug)]
+pub struct App {
+    /// Is the application running?
+    pub running: bool,
+
+    pub verbose: bool,
+    pub mode: AppMode,
+    pub color_mode: utils::colors::ColorMode,
+    /// RGB color of NaN and infinite voxels.
+    pub nan_color: [u8; 3],
+    pub renderer: widgets::slice_widget::Renderer,
+    /// Graphics protocol renderer offered when cycling renderers, if supported by the terminal.
+    pub graphics_renderer: Option<widgets::slice_widget::Renderer>,
+    /// Opened images, one per tab.
+    pub images: Vec<ImageState>,
+    /// Index of the currently displayed image.
+    pub image_index: usize,
+    pub compare_view: CompareView,
+    pub layout: widgets::slice_widget::XyzLayout,
+    /// Axis shown by [`widgets::slice_widget::XyzLayout::Single`] and
+    /// [`widgets::slice_widget::XyzLayout::Mosaic`] (0: axial, 1: coronal, 2: sagittal).
+    pub single_axis: usize,
+    /// Number of slices shown by [`widgets::slice_widget::XyzLayout::Mosaic`].
+    pub mosaic_slices: usize,
+    /// Display patient left on screen right instead of on screen left.
+    pub radiological: bool,
+    /// Whether slices are displayed along the world or the array axes,
+    /// and the rotation of oblique world space slices.
+    pub frame: utils::sampling::SliceFrame,
+    /// Thin slices or intensity projections through the volume.
+    pub projection: utils::sampling::Projection,
+    /// Whether the flicker view shows the next image instead of the current one.
+    pub flicker_phase: bool,
+    /// Whether the flicker view alternates automatically on every tick.
+    pub flicker_running: bool,
+    /// Magnification of the slices, 1 shows the whole volume.
+    pub zoom: f64,
+    /// Offset of the view center from the crosshair in world space.
+    pub pan: [f64; 3],
+    /// Slice placements and pixel images of the last frame.
+    pub slice_output: widgets::slice_widget::SliceOutput,
+    /// Names of the values of label images, used by [`App::go_to`].
+    pub labels: Option<utils::labels::LabelTable>,
+    /// Text typed into the go-to prompt, `None` if the prompt is closed.
+    pub prompt: Option<String>,
+    /// Outcome of the last go-to or jump command, shown until the next key press.
+    pub message: Option<Result<String, String>>,
+    /// Voxels above this value form the clusters listed in [`AppMode::Clusters`]
+    /// and cycled by [`App::next_peak`], half of the maximum of each image if `None`.
+    pub cluster_threshold: Option<f64>,
+    pub connectivity: utils::clusters::Connectivity,
+    /// Shape of the region of interest whose statistics are shown, `None` to hide them.
+    pub roi_shape: Option<utils::roi::RoiShape>,
+    /// Radius of box and sphere regions of interest in world units.
+    pub roi_radius: f64,
+    /// Region of interest for [`utils::roi::RoiShape::Mask`].
+    pub mask: Option<utils::brain_volume::BrainVolume>,
+    /// Number of input files that could not be loaded, shown in the title bar.
+    pub failed_files: usize,
+}
+
+impl App {
+    /// Constructs a new instance of [`App`].
+    ///
+    /// `images` must contain at least one image. A graphics protocol `renderer`
+    /// remains available when switching to character renderers.
+    pub fn new(
+        verbose: bool,
+        images: Vec<ImageState>,
+        color_mode: utils::colors::ColorMode,
+        renderer: widgets::slice_widget::Renderer,
+    ) -> Self {
+        assert!(!images.is_empty(), "At least one image is required");
+        Self {
             verbose,
             running: true,
-            file_path: file_path.to_string(),
-            volume: volume,
-            image_cache: utils::slice_cache::SliceCache::new(),
-            intensity_range: intensity_range,
-            slice_position: middle_slice,
-            increment: increment,
             mode: AppMode::Xyz,
-            color_map: color_map,
-            color_mode: color_mode,
-            metadata: metadata,
-            metadata_index: 0,
-        })
+            color_mode,
+            nan_color: utils::colors::DEFAULT_NAN_COLOR,
+            renderer,
+            graphics_renderer: match renderer {
+                widgets::slice_widget::Renderer::Graphics { .. } => Some(renderer),
+                _ => None,
+            },
+            images,
+            image_index: 0,
+            compare_view: CompareView::Grid,
+            layout: widgets::slice_widget::XyzLayout::Row,
+            single_axis: 0,
+            mosaic_slices: 12,
+            radiological: false,
+            frame: utils::sampling::SliceFrame::new(utils::sampling::SliceSpace::World),
+            projection: utils::sampling::Projection::Slice,
+            flicker_phase: false,
+            flicker_running: true,
+            zoom: 1.,
+            pan: [0.; 3],
+            slice_output: widgets::slice_widget::SliceOutput::default(),
+            labels: None,
+            prompt: None,
+            message: None,
+            cluster_threshold: None,
+            connectivity: utils::clusters::Connectivity::Corners,
+            roi_shape: None,
+            roi_radius: DEFAULT_ROI_RADIUS,
+            mask: None,
+            failed_files: 0,
+        }
+    }
+
+    /// Currently displayed image.
+    pub fn image(&self) -> &ImageState {
+        &self.images[self.image_index]
+    }
+
+    /// Currently displayed image.
+    pub fn image_mut(&mut self) -> &mut ImageState {
+        &mut self.images[self.image_index]
+    }
+
+    /// Index of the image the current one is compared against in the
+    /// flicker and checkerboard views.
+    pub fn partner_index(&self) -> usize {
+        (self.image_index + 1) % self.images.len()
+    }
+
+    /// Current image and the image it is compared against.
+    pub fn image_and_partner_mut(&mut self) -> (&mut ImageState, &mut ImageState) {
+        let (index, partner_index) = (self.image_index, self.partner_index());
+        assert_ne!(index, partner_index, "Comparison requires two images");
+        if index < partner_index {
+            let (head, tail) = self.images.split_at_mut(partner_index);
+            (&mut head[index], &mut tail[0])
+        } else {
+            let (head, tail) = self.images.split_at_mut(index);
+            (&mut tail[0], &mut head[partner_index])
+        }
     }
 
     /// Handles the tick event of the terminal.
-    pub fn tick(&self) {}
+    pub fn tick(&mut self) {
+        if let (AppMode::Compare, CompareView::Flicker, true) =
+            (self.mode, self.compare_view, self.flicker_running)
+        {
+            self.flicker_phase = !self.flicker_phase;
+        }
+    }
 
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
         self.running = false;
     }
 
-    pub fn increment_slice(&mut self, axis: usize) {
-        let (_, max) = match axis {
-            0 => (self.volume.world_bounds.x0, self.volume.world_bounds.x1),
-            1 => (self.volume.world_bounds.y0, self.volume.world_bounds.y1),
-            2 => (self.volume.world_bounds.z0, self.volume.world_bounds.z1),
-            _ => (0.0, 0.0),
-        };
+    pub fn next_image(&mut self) {
+        self.image_index = (self.image_index + 1) % self.images.len();
+    }
 
-        match axis {
-            0..=2 => {
-                if self.slice_position[axis] <= max - self.increment {
-                    self.slice_position[axis] += self.increment;
-                }
-            }
-            _ => {}
+    pub fn previous_image(&mut self) {
+        self.image_index = (self.image_index + self.images.len() - 1) % self.images.len();
+    }
+
+    /// Moves the crosshair by `steps` voxels along a world axis, an array axis
+    /// in voxel space or a rotated axis for oblique slices.
+    pub fn increment_slice(&mut self, axis: usize, steps: f64) {
+        let frame = self.display_frame();
+        match frame.space {
+            _ if frame.oblique() => self.step_oblique(axis, steps),
+            utils::sampling::SliceSpace::World => self.image_mut().step_slice(axis, steps),
+            utils::sampling::SliceSpace::Voxel => self.image_mut().step_voxel(axis, steps),
         }
+        self.link_slice_positions();
     }
 
-    pub fn decrement_slice(&mut self, axis: usize) {
-        let (min, _) = match axis {
-            0 => (self.volume.world_bounds.x0, self.volume.world_bounds.x1),
-            1 => (self.volume.world_bounds.y0, self.volume.world_bounds.y1),
-            2 => (self.volume.world_bounds.z0, self.volume.world_bounds.z1),
-            _ => (0.0, 0.0),
-        };
+    pub fn decrement_slice(&mut self, axis: usize, steps: f64) {
+        self.increment_slice(axis, -steps);
+    }
 
-        match axis {
-            0..=2 => {
-                if self.slice_position[axis] >= min + self.increment {
-                    self.slice_position[axis] -= self.increment;
-                }
+    /// Moves the crosshair along a rotated axis, stopping at the world bounds.
+    fn step_oblique(&mut self, axis: usize, steps: f64) {
+        let frame = self.display_frame();
+        let image = self.image_mut();
+        let mut position = image.position_in(&frame);
+        position[axis] += steps * image.volume.voxel_size();
+        let previous = image.slice_position.clone();
+        image.set_position_in(&position, &frame);
+        let bounds = image.volume.world_bounds;
+        let (min, max) = (bounds.min(), bounds.max());
+        if (0..3).any(|a| image.slice_position[a] < min[a] || image.slice_position[a] > max[a]) {
+            image.slice_position = previous;
+        }
+    }
+
+    /// In compare mode, moves the crosshairs of all images to the
+    /// world position of the current image.
+    fn link_slice_positions(&mut self) {
+        if let AppMode::Compare = self.mode {
+            let position = self.image().slice_position.clone();
+            for image in self.images.iter_mut() {
+                image.slice_position.clone_from(&position);
             }
-            _ => {}
         }
     }
 
     pub fn toggle_tab(&mut self) {
+        let space = self.display_frame().space;
         self.mode = match self.mode {
             AppMode::Xyz => AppMode::MetaData,
-            AppMode::MetaData => AppMode::Xyz,
-        }
+            AppMode::MetaData => AppMode::Clusters,
+            AppMode::Clusters if self.images.len() > 1 => AppMode::Compare,
+            AppMode::Clusters => AppMode::Xyz,
+            AppMode::Compare => AppMode::Xyz,
+        };
+        self.reset_pan_if_space_changed(space);
+        self.link_slice_positions();
     }
 
     pub fn toggle_color_map(&mut self) {
-        self.color_map = self.color_map.next();
+        self.image_mut().toggle_color_map();
     }
 
-    pub fn increment_metadata_index(&mut self) {
-        if self.metadata_index < self.metadata.len() - 2 {
-            self.metadata_index += 1;
+    pub fn change_display_threshold(&mut self, upper: bool, direction: f64) {
+        self.image_mut().change_display_threshold(upper, direction);
+    }
+
+    pub fn toggle_symmetric_range(&mut self) {
+        self.image_mut().toggle_symmetric_range();
+    }
+
+    /// Cycles through the character renderers and, if available, the graphics protocol.
+    pub fn toggle_renderer(&mut self) {
+        use widgets::slice_widget::Renderer;
+        self.renderer = match self.renderer {
+            Renderer::HalfBlock => Renderer::Quadrant,
+            Renderer::Quadrant => Renderer::Sextant,
+            Renderer::Sextant => Renderer::Braille,
+            Renderer::Braille => self.graphics_renderer.unwrap_or(Renderer::HalfBlock),
+            Renderer::Graphics { .. } => Renderer::HalfBlock,
+        };
+    }
+
+    pub fn toggle_layout(&mut self) {
+        self.layout = self.layout.next();
+    }
+
+    /// Shows the next axis (axial, coronal, sagittal) in the single panel and mosaic layouts.
+    pub fn toggle_single_axis(&mut self) {
+        use widgets::slice_widget::XyzLayout;
+        if let XyzLayout::Single | XyzLayout::Mosaic = self.layout {
+            self.single_axis = (self.single_axis + 1) % 3;
+        } else {
+            self.layout = XyzLayout::Single;
         }
     }
 
-    pub fn decrement_metadata_index(&mut self) {
-        if self.metadata_index > 0 {
-            self.metadata_index -= 1;
+    /// Switches between neurological and radiological display convention.
+    pub fn toggle_radiological(&mut self) {
+        self.radiological = !self.radiological;
+    }
+
+    /// Cycles through thin slices and maximum, minimum and mean intensity projections.
+    pub fn cycle_projection(&mut self) {
+        self.projection = self.projection.next();
+    }
+
+    /// Whether screen right is patient left, i.e. radiological convention in world space.
+    pub fn mirrored(&self) -> bool {
+        self.radiological && self.display_frame().space == utils::sampling::SliceSpace::World
+    }
+
+    /// Coordinate system of the displayed slices.
+    ///
+    /// The flicker and checkerboard views sample both images on the grid of the
+    /// current one, which only lines up in world space, so they fall back to
+    /// world space in voxel space.
+    pub fn display_frame(&self) -> utils::sampling::SliceFrame {
+        match (self.mode, self.compare_view, self.frame.space) {
+            (
+                AppMode::Compare,
+                CompareView::Flicker | CompareView::Checkerboard,
+                utils::sampling::SliceSpace::Voxel,
+            ) => utils::sampling::SliceFrame::new(utils::sampling::SliceSpace::World),
+            _ => self.frame,
         }
     }
+
+    /// Switches between displaying slices in world space and along the array axes.
+    pub fn toggle_space(&mut self) {
+        self.frame.space = match self.frame.space {
+            utils::sampling::SliceSpace::World => utils::sampling::SliceSpace::Voxel,
+            utils::sampling::SliceSpace::Voxel => utils::sampling::SliceSpace::World,
+        };
+        // the offset is measured in the units of the space
+        self.pan = [0.; 3];
+    }
+
+    /// Resets the view offset if the displayed space differs from `previous`.
+    fn reset_pan_if_space_changed(&mut self, previous: utils::sampling::SliceSpace) {
+        if self.display_frame().space != previous {
+            self.pan = [0.; 3];
+        }
+    }
+
+    pub fn increment_mosaic_slices(&mut self) {
+        self.mosaic_slices = (self.mosaic_slices + 1).min(MAX_MOSAIC_SLICES);
+    }
+
+    pub fn decrement_mosaic_slices(&mut self) {
+        self.mosaic_slices = (self.mosaic_slices - 1).max(1);
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom = (self.zoom * ZOOM_STEP).min(MAX_ZOOM);
+        self.clamp_pan();
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom / ZOOM_STEP).max(1.);
+        self.clamp_pan();
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.zoom = 1.;
+        self.pan = [0.; 3];
+    }
+
+    /// Rotates the slices around the crosshair about a world axis.
+    pub fn rotate(&mut self, axis: usize, degrees: f64) {
+        // The planes through the crosshair do not depend on the pivot,
+        // moving it to the crosshair keeps the crosshair in place.
+        let position = &self.image().slice_position;
+        self.frame.pivot = [position[0], position[1], position[2]];
+        self.pan = [0.; 3];
+
+        // keep within (-180, 180]
+        let angle = (self.frame.rotation[axis] + degrees) % 360.;
+        self.frame.rotation[axis] = if angle > 180. {
+            angle - 360.
+        } else if angle <= -180. {
+            angle + 360.
+        } else {
+            angle
+        };
+    }
+
+    pub fn reset_rotation(&mut self) {
+        self.frame.rotation = [0.; 3];
+    }
+
+    /// Moves the view by a tenth of its size along a world or array axis.
+    pub fn pan(&mut self, axis: usize, direction: f64) {
+        let size = self.image().volume.bounds(self.display_frame().space).size()[axis];
+        self.pan[axis] += direction * size / self.zoom / 10.;
+        self.clamp_pan();
+    }
+
+    /// Keeps the view within the bounds of the current image.
+    fn clamp_pan(&mut self) {
+        let frame = self.display_frame();
+        let image = &self.images[self.image_index];
+        let bounds = image.volume.bounds(frame.space);
+        let (min, max, size) = (bounds.min(), bounds.max(), bounds.size());
+        let position = image.position_in(&frame);
+        for axis in 0..3 {
+            let half = size[axis] / self.zoom / 2.;
+            self.pan[axis] = self.pan[axis]
+                .min(max[axis] - half - position[axis])
+                .max(min[axis] + half - position[axis]);
+        }
+    }
+
+    /// Moves the crosshair to the position below a terminal cell
+    /// of the last frame, without moving the view.
+    pub fn click(&mut self, column: u16, row: u16) {
+        let Some((placement, clicked)) = self.slice_output.position(column, row) else {
+            return;
+        };
+        let depth = (placement.axis, placement.depth);
+        let frame = self.display_frame();
+        let image = &mut self.images[self.image_index];
+        let mut position = image.position_in(&frame);
+        for (axis, value) in clicked.into_iter().chain([depth]) {
+            self.pan[axis] += position[axis] - value;
+            position[axis] = value;
+        }
+        image.set_position_in(&position, &frame);
+        self.link_slice_positions();
+    }
+
+    pub fn open_prompt(&mut self) {
+        self.prompt = Some(String::new());
+    }
+
+    /// Closes the prompt and jumps to the typed target.
+    pub fn submit_prompt(&mut self) {
+        if let Some(target) = self.prompt.take() {
+            if let Err(e) = self.go_to(&target) {
+                self.message = Some(Err(e.to_string()));
+            }
+        }
+    }
+
+    /// Moves the crosshair to a target typed by the user:
+    ///
+    /// - `x, y, z` world coordinates (optionally followed by `mm`)
+    /// - `v i, j, k` voxel indices of the current image
+    /// - the name of a label in [`App::labels`], found in the current or another opened image
+    pub fn go_to(&mut self, target: &str) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        let target = target.trim();
+        let voxel = target
+            .strip_prefix(['v', 'V'])
+            .and_then(parse_coordinates);
+        // typed in the order (x, y, z) and (i, j, k), the array axes are reversed
+        let position = if let Some([i, j, k]) = voxel {
+            let volume = &self.image().volume;
+            let voxel = [k, j, i].map(f64::round);
+            let max = volume.local_bounds.max();
+            if (0..3).any(|a| voxel[a] < 0. || voxel[a] > max[a]) {
+                return Err(format!(
+                    "Voxel {}, {}, {} is outside of the image",
+                    voxel[2], voxel[1], voxel[0]
+                )
+                .into());
+            }
+            volume.voxel_to_world(&voxel)
+        } else if let Some([x, y, z]) = parse_coordinates(target.trim_end_matches("mm")) {
+            let position = [z, y, x];
+            let bounds = self.image().volume.world_bounds;
+            let (min, max) = (bounds.min(), bounds.max());
+            if (0..3).any(|a| position[a] < min[a] || position[a] > max[a]) {
+                return Err(format!("{}, {}, {} is outside of the image", x, y, z).into());
+            }
+            position
+        } else {
+            let labels = self
+                .labels
+                .as_ref()
+                .ok_or("Not a coordinate, and no label names loaded (--labels)")?;
+            let (value, name) = labels.find(target)?;
+            // the label image may be opened in another tab
+            let order = (0..self.images.len()).map(|i| (self.image_index + i) % self.images.len());
+            order
+                .filter_map(|i| {
+                    let volume = &self.images[i].volume;
+                    volume
+                        .region_center(value as f64)
+                        .map(|[i, j, k]| volume.voxel_to_world(&[i as f64, j as f64, k as f64]))
+                })
+                .next()
+                .ok_or_else(|| format!("Label '{}' ({}) not found in the opened images", name, value))?
+        };
+
+        self.image_mut().slice_position = position.to_vec();
+        self.pan = [0.; 3];
+        self.link_slice_positions();
+        Ok(())
+    }
+
+    /// Moves the crosshair to the voxel with the largest or smallest value.
+    pub fn go_to_extremum(&mut self, maximum: bool) {
+        let volume = &self.image().volume;
+        let Some((min, max)) = utils::argminmax2::argminmax_3d(&volume.array_view_3d()) else {
+            self.message = Some(Err("No finite voxels".to_owned()));
+            return;
+        };
+        let voxel = if maximum { max } else { min };
+        let value = volume.array_view_3d()[voxel];
+        self.message = Some(Ok(format!(
+            "{} {:.3} at voxel {}, {}, {}",
+            if maximum { "Maximum" } else { "Minimum" },
+            value,
+            voxel[2],
+            voxel[1],
+            voxel[0]
+        )));
+        self.go_to_voxel(voxel);
+    }
+
+    /// Threshold of the clusters of the current image.
+    pub fn cluster_threshold(&self) -> f64 {
+        self.cluster_threshold
+            .unwrap_or(self.image().volume.intensity_range.1 / 2.)
+    }
+
+    /// Clusters of the current image, found on first use.
+    pub fn clusters(&mut self) -> &[utils::clusters::Cluster] {
+        let (threshold, connectivity) = (self.cluster_threshold(), self.connectivity);
+        let image = self.image_mut();
+        image.clusters.get_or_insert_with(|| {
+            utils::clusters::find_clusters(&image.volume.array_view_3d(), threshold, connectivity)
+        })
+    }
+
+    /// Rows of the cluster table: index, size, peak value and peak world position.
+    pub fn cluster_table(&mut self) -> widgets::table_widget::Table {
+        let clusters = self.clusters().to_vec();
+        let volume = &self.image().volume;
+        clusters
+            .iter()
+            .enumerate()
+            .map(|(index, cluster)| {
+                let [i, j, k] = cluster.peak;
+                let peak = volume.voxel_to_world(&[i as f64, j as f64, k as f64]);
+                vec![
+                    format!("{}", index + 1),
+                    format!("{}", cluster.size),
+                    format!("{:.3}", cluster.peak_value),
+                    format!("{:.1}", peak[2]),
+                    format!("{:.1}", peak[1]),
+                    format!("{:.1}", peak[0]),
+                ]
+            })
+            .collect()
+    }
+
+    /// Moves the crosshair to the peak of the next (or previous for negative `step`) cluster
+    /// above [`App::cluster_threshold`], in order of decreasing peak value.
+    pub fn next_peak(&mut self, step: isize) {
+        let threshold = self.cluster_threshold();
+        let n = self.clusters().len();
+        if n == 0 {
+            self.message = Some(Err(format!("No clusters above {:.3}", threshold)));
+            return;
+        }
+        let image = self.image_mut();
+        let index = match image.peak_index {
+            Some(index) => (index as isize + step).rem_euclid(n as isize) as usize,
+            None if step < 0 => n - 1,
+            None => 0,
+        };
+        image.peak_index = Some(index);
+        let cluster = &self.clusters()[index];
+        let (voxel, peak_value, size) = (cluster.peak, cluster.peak_value, cluster.size);
+        self.message = Some(Ok(format!(
+            "Peak {}/{}: {:.3} at voxel {}, {}, {} (cluster of {} voxels above {:.3})",
+            index + 1,
+            n,
+            peak_value,
+            voxel[2],
+            voxel[1],
+            voxel[0],
+            size,
+            threshold
+        )));
+        self.go_to_voxel(voxel);
+    }
+
+    /// Raises or lowers the cluster threshold by a step of 5 % of the maximum.
+    pub fn change_cluster_threshold(&mut self, direction: f64) {
+        let step = self.image().volume.intensity_range.1.abs() * CLUSTER_THRESHOLD_STEP;
+        self.cluster_threshold = Some(self.cluster_threshold() + direction * step);
+        self.invalidate_clusters();
+    }
+
+    /// Cycles between 6, 18 and 26 connected clusters.
+    pub fn toggle_connectivity(&mut self) {
+        self.connectivity = self.connectivity.next();
+        self.invalidate_clusters();
+    }
+
+    fn invalidate_clusters(&mut self) {
+        for image in self.images.iter_mut() {
+            image.clusters = None;
+            image.peak_index = None;
+        }
+    }
+
+    /// Cycles through hidden, box, sphere and, if a mask is loaded, mask regions of interest.
+    pub fn toggle_roi(&mut self) {
+        use utils::roi::RoiShape;
+        self.roi_shape = match self.roi_shape {
+            None => Some(RoiShape::Box),
+            Some(RoiShape::Box) => Some(RoiShape::Sphere),
+            Some(RoiShape::Sphere) if self.mask.is_some() => Some(RoiShape::Mask),
+            Some(RoiShape::Sphere) | Some(RoiShape::Mask) => None,
+        };
+    }
+
+    /// Grows or shrinks box and sphere regions of interest by one voxel.
+    pub fn change_roi_radius(&mut self, direction: f64) {
+        let step = self.image().volume.voxel_size();
+        self.roi_radius = (self.roi_radius + direction * step).max(0.);
+    }
+
+    /// Statistics of the region of interest in the current image, if shown.
+    pub fn roi_stats(&mut self) -> Option<(utils::roi::Roi, utils::roi::RoiStats)> {
+        let roi = utils::roi::Roi {
+            shape: self.roi_shape?,
+            radius: self.roi_radius,
+        };
+        let mask = self.mask.as_ref();
+        let image = &mut self.images[self.image_index];
+        // masks do not move with the crosshair
+        let center = match roi.shape {
+            utils::roi::RoiShape::Mask => Vec::new(),
+            _ => image.slice_position.clone(),
+        };
+        match &image.roi_stats {
+            Some((cached_roi, cached_center, stats)) if *cached_roi == roi && *cached_center == center => {
+                Some((roi, stats.clone()))
+            }
+            _ => {
+                let voxels = roi.voxels(&image.volume, &image.slice_position, mask);
+                let stats = utils::roi::RoiStats::new(&image.volume, &voxels);
+                image.roi_stats = Some((roi, center, stats.clone()));
+                Some((roi, stats))
+            }
+        }
+    }
+
+    fn go_to_voxel(&mut self, [i, j, k]: [usize; 3]) {
+        let image = self.image_mut();
+        image.slice_position = image.volume.voxel_to_world(&[i as f64, j as f64, k as f64]).to_vec();
+        self.pan = [0.; 3];
+        self.link_slice_positions();
+    }
+
+    pub fn toggle_compare_view(&mut self) {
+        let space = self.display_frame().space;
+        self.compare_view = self.compare_view.next();
+        self.flicker_phase = false;
+        self.flicker_running = true;
+        self.reset_pan_if_space_changed(space);
+    }
+
+    /// Manually switches the flicker view between both images and stops
+    /// the automatic alternation.
+    pub fn flicker(&mut self) {
+        self.flicker_running = false;
+        self.flicker_phase = !self.flicker_phase;
+    }
+
+    pub fn increment_metadata_index(&mut self) {
+        self.image_mut().increment_metadata_index();
+    }
+
+    pub fn decrement_metadata_index(&mut self) {
+        self.image_mut().decrement_metadata_index();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// App showing a 6 x 5 x 4 voxel image with x = 2 i - 10, y = j and z = 3 k,
+    /// and label 2 at voxel (1, 2, 3).
+    fn app() -> App {
+        let mut arr = ndarray::Array3::<f64>::zeros((4, 5, 6));
+        // array axes are (k, j, i)
+        arr[[3, 2, 1]] = 2.;
+        let header = nifti::NiftiHeader {
+            dim: [3, 6, 5, 4, 1, 1, 1, 1],
+            datatype: nifti::NiftiType::Float64 as i16,
+            srow_x: [2., 0., 0., -10.],
+            srow_y: [0., 1., 0., 0.],
+            srow_z: [0., 0., 3., 0.],
+            ..Default::default()
+        };
+        let volume = utils::brain_volume::BrainVolume::from_array(arr.into_dyn(), header);
+        let image = ImageState::from_volume("labels.nii.gz", volume);
+        App::new(
+            false,
+            vec![image],
+            utils::colors::ColorMode::TrueColor,
+            widgets::slice_widget::Renderer::HalfBlock,
+        )
+    }
+
+    fn error(result: Result<(), Box<dyn error::Error + Send + Sync>>) -> String {
+        result.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn coordinates() {
+        assert_eq!(parse_coordinates("1, 2, 3"), Some([1., 2., 3.]));
+        assert_eq!(parse_coordinates(" (-42, 18.5,6) "), Some([-42., 18.5, 6.]));
+        assert_eq!(parse_coordinates("[1 2\t3]"), Some([1., 2., 3.]));
+        assert_eq!(parse_coordinates("1,, 2 ,3"), Some([1., 2., 3.]));
+        assert_eq!(parse_coordinates("1, 2"), None);
+        assert_eq!(parse_coordinates("1, 2, 3, 4"), None);
+        assert_eq!(parse_coordinates("1, 2, z"), None);
+        assert_eq!(parse_coordinates(""), None);
+    }
+
+    #[test]
+    fn go_to_world_coordinates() {
+        let mut app = app();
+        app.go_to("-8, 2, 9").unwrap();
+        // world positions are (z, y, x)
+        assert_eq!(app.image().slice_position, vec![9., 2., -8.]);
+        app.go_to("(0 4 3) mm").unwrap();
+        assert_eq!(app.image().slice_position, vec![3., 4., 0.]);
+
+        assert_eq!(error(app.go_to("-12, 2, 9")), "-12, 2, 9 is outside of the image");
+        assert_eq!(app.image().slice_position, vec![3., 4., 0.]);
+    }
+
+    #[test]
+    fn go_to_voxel() {
+        let mut app = app();
+        app.go_to("v 1, 2, 3").unwrap();
+        assert_eq!(app.image().slice_position, vec![9., 2., -8.]);
+        app.go_to("V(5 0 0)").unwrap();
+        assert_eq!(app.image().slice_position, vec![0., 0., 0.]);
+        // rounded to the nearest voxel
+        app.go_to("v 0.8, 1.2, 0").unwrap();
+        assert_eq!(app.image().slice_position, vec![0., 1., -8.]);
+
+        assert_eq!(error(app.go_to("v 6, 0, 0")), "Voxel 6, 0, 0 is outside of the image");
+        assert_eq!(error(app.go_to("v 0, 0, -1")), "Voxel 0, 0, -1 is outside of the image");
+    }
+
+    #[test]
+    fn go_to_label() {
+        let mut app = app();
+        assert_eq!(
+            error(app.go_to("hippocampus")),
+            "Not a coordinate, and no label names loaded (--labels)"
+        );
+
+        app.labels = Some(utils::labels::LabelTable::parse(
+            "1 Left-Hippocampus\n2 Right-Hippocampus\n",
+        ));
+        app.go_to("right-hippocampus").unwrap();
+        assert_eq!(app.image().slice_position, vec![9., 2., -8.]);
+        assert_eq!(
+            error(app.go_to("Left-Hippocampus")),
+            "Label 'Left-Hippocampus' (1) not found in the opened images"
+        );
+        assert_eq!(error(app.go_to("hippocampus")), "'hippocampus' matches 2 labels");
+        // malformed coordinates are taken as label names
+        assert_eq!(error(app.go_to("1, 2")), "Unknown label '1, 2'");
+    }
 }
@@ -1,11 +1,32 @@
 use crate::app::App;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+/// Voxels the crosshair moves per key press with Shift.
+const COARSE_STEPS: f64 = 10.;
+
+/// Degrees the slices are rotated per key press.
+const ROTATION_STEP: f64 = 5.;
 
 /// Handles the key events and updates the state of [`App`].
 pub fn handle_key_events(
     key_event: KeyEvent,
     app: &mut App,
 ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // The go-to prompt receives all keys while open
+    if let Some(input) = app.prompt.as_mut() {
+        match key_event.code {
+            KeyCode::Enter => app.submit_prompt(),
+            KeyCode::Esc => app.prompt = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+        return Ok(());
+    }
+    app.message = None;
+
     match key_event.code {
         // Exit application on `ESC` or `q`
         KeyCode::Esc | KeyCode::Char('q') => {
@@ -13,36 +34,75 @@ pub fn handle_key_events(
             return Ok(());
         }
         // Exit application on `Ctrl-C`
-        KeyCode::Char('c') | KeyCode::Char('C') => {
-            if key_event.modifiers == KeyModifiers::CONTROL {
-                app.quit();
-                return Ok(());
-            }
+        KeyCode::Char('c') | KeyCode::Char('C') if key_event.modifiers == KeyModifiers::CONTROL => {
+            app.quit();
+            return Ok(());
+        }
+        // Switch between opened images
+        KeyCode::Char('n') | KeyCode::PageDown => {
+            app.next_image();
+            return Ok(());
+        }
+        KeyCode::Char('p') | KeyCode::PageUp => {
+            app.previous_image();
+            return Ok(());
         }
         _ => {}
     }
 
     match app.mode {
-        crate::app::AppMode::Xyz => {
+        crate::app::AppMode::Xyz | crate::app::AppMode::Compare => {
+            let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+            // screen right is patient left in radiological convention
+            let right = if app.mirrored() { -1. } else { 1. };
+            // Shift moves the crosshair in coarse steps
+            let shift = key_event.modifiers.contains(KeyModifiers::SHIFT)
+                || matches!(key_event.code, KeyCode::Char(c) if c.is_ascii_uppercase());
+            let steps = if shift { COARSE_STEPS } else { 1. };
             match key_event.code {
+                // Move the zoomed in view
+                KeyCode::Right if ctrl => {
+                    app.pan(2, right);
+                }
+                KeyCode::Left if ctrl => {
+                    app.pan(2, -right);
+                }
+                KeyCode::Up if ctrl => {
+                    app.pan(1, 1.);
+                }
+                KeyCode::Down if ctrl => {
+                    app.pan(1, -1.);
+                }
+                KeyCode::Char('z') | KeyCode::Char('y') if ctrl => {
+                    app.pan(0, 1.);
+                }
+                KeyCode::Char('x') if ctrl => {
+                    app.pan(0, -1.);
+                }
                 // Counter handlers
-                KeyCode::Right | KeyCode::Char('d') => {
-                    app.increment_slice(2);
+                KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('D') if app.mirrored() => {
+                    app.decrement_slice(2, steps);
+                }
+                KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('A') if app.mirrored() => {
+                    app.increment_slice(2, steps);
+                }
+                KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('D') => {
+                    app.increment_slice(2, steps);
                 }
-                KeyCode::Left | KeyCode::Char('a') => {
-                    app.decrement_slice(2);
+                KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('A') => {
+                    app.decrement_slice(2, steps);
                 }
-                KeyCode::Up | KeyCode::Char('w') => {
-                    app.increment_slice(1);
+                KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('W') => {
+                    app.increment_slice(1, steps);
                 }
-                KeyCode::Down | KeyCode::Char('s') => {
-                    app.decrement_slice(1);
+                KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('S') => {
+                    app.decrement_slice(1, steps);
                 }
-                KeyCode::Char('x') => {
-                    app.decrement_slice(0);
+                KeyCode::Char('x') | KeyCode::Char('X') => {
+                    app.decrement_slice(0, steps);
                 }
-                KeyCode::Char('z') | KeyCode::Char('y') => {
-                    app.increment_slice(0);
+                KeyCode::Char('z') | KeyCode::Char('y') | KeyCode::Char('Z') | KeyCode::Char('Y') => {
+                    app.increment_slice(0, steps);
                 }
                 KeyCode::Tab => {
                     app.toggle_tab();
@@ -50,9 +110,133 @@ pub fn handle_key_events(
                 KeyCode::Char('c') | KeyCode::Char('C') => {
                     app.toggle_color_map();
                 }
+                KeyCode::Char('r') | KeyCode::Char('R') => {
+                    app.toggle_renderer();
+                }
+                KeyCode::Char('l') | KeyCode::Char('L') => {
+                    app.toggle_layout();
+                }
+                KeyCode::Char('o') | KeyCode::Char('O') => {
+                    app.toggle_single_axis();
+                }
+                // Rotate the slices around the crosshair
+                KeyCode::Char('h') => {
+                    app.rotate(0, ROTATION_STEP);
+                }
+                KeyCode::Char('H') => {
+                    app.rotate(0, -ROTATION_STEP);
+                }
+                KeyCode::Char('j') => {
+                    app.rotate(1, ROTATION_STEP);
+                }
+                KeyCode::Char('J') => {
+                    app.rotate(1, -ROTATION_STEP);
+                }
+                KeyCode::Char('k') => {
+                    app.rotate(2, ROTATION_STEP);
+                }
+                KeyCode::Char('K') => {
+                    app.rotate(2, -ROTATION_STEP);
+                }
+                KeyCode::Char('t') | KeyCode::Char('T') => {
+                    app.reset_rotation();
+                }
+                KeyCode::Char(':') | KeyCode::Char('g') | KeyCode::Char('G') => {
+                    app.open_prompt();
+                }
+                // Jump to extrema and cluster peaks
+                KeyCode::Char('e') => {
+                    app.go_to_extremum(true);
+                }
+                KeyCode::Char('E') => {
+                    app.go_to_extremum(false);
+                }
+                KeyCode::Char('.') => {
+                    app.next_peak(1);
+                }
+                KeyCode::Char(',') => {
+                    app.next_peak(-1);
+                }
+                // Region of interest statistics
+                KeyCode::Char('b') | KeyCode::Char('B') => {
+                    app.toggle_roi();
+                }
+                KeyCode::Char('>') => {
+                    app.change_roi_radius(1.);
+                }
+                KeyCode::Char('<') => {
+                    app.change_roi_radius(-1.);
+                }
+                KeyCode::Char('|') => {
+                    app.toggle_symmetric_range();
+                }
+                // Display thresholds
+                KeyCode::Char(')') => {
+                    app.change_display_threshold(false, 1.);
+                }
+                KeyCode::Char('(') => {
+                    app.change_display_threshold(false, -1.);
+                }
+                KeyCode::Char('}') => {
+                    app.change_display_threshold(true, 1.);
+                }
+                KeyCode::Char('{') => {
+                    app.change_display_threshold(true, -1.);
+                }
+                KeyCode::Char('m') | KeyCode::Char('M') => {
+                    app.cycle_projection();
+                }
+                KeyCode::Char('i') | KeyCode::Char('I') => {
+                    app.toggle_space();
+                }
+                KeyCode::Char('f') | KeyCode::Char('F') => {
+                    app.toggle_radiological();
+                }
+                KeyCode::Char(']') => {
+                    app.increment_mosaic_slices();
+                }
+                KeyCode::Char('[') => {
+                    app.decrement_mosaic_slices();
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    app.zoom_in();
+                }
+                KeyCode::Char('-') => {
+                    app.zoom_out();
+                }
+                KeyCode::Char('0') => {
+                    app.reset_zoom();
+                }
+                KeyCode::Char('v') if matches!(app.mode, crate::app::AppMode::Compare) => {
+                    app.toggle_compare_view();
+                }
+                KeyCode::Char(' ') if matches!(app.mode, crate::app::AppMode::Compare) => {
+                    app.flicker();
+                }
                 _ => {}
             }
         }
+        crate::app::AppMode::Clusters => match key_event.code {
+            KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('.') => {
+                app.next_peak(1);
+            }
+            KeyCode::Up | KeyCode::Char('w') | KeyCode::Char(',') => {
+                app.next_peak(-1);
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                app.change_cluster_threshold(1.);
+            }
+            KeyCode::Char('-') => {
+                app.change_cluster_threshold(-1.);
+            }
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                app.toggle_connectivity();
+            }
+            KeyCode::Tab => {
+                app.toggle_tab();
+            }
+            _ => {}
+        },
         crate::app::AppMode::MetaData => match key_event.code {
             KeyCode::Right | KeyCode::Char('d') | KeyCode::Down | KeyCode::Char('s') => {
                 app.increment_metadata_index();
@@ -70,25 +254,27 @@ pub fn handle_key_events(
     Ok(())
 }
 
+/// Handles the mouse events and updates the state of [`App`].
+///
+/// Clicking or dragging on a slice moves the crosshair, scrolling zooms.
 pub fn handle_mouse_events(
-    _mouse_event: MouseEvent,
-    _app: &mut App,
+    mouse_event: MouseEvent,
+    app: &mut App,
 ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    /*match mouse_event.kind {
-        crossterm::event::MouseEventKind::Down(button) => {
-            match button {
-                crossterm::event::MouseButton::Left => {
-                    println!("row: {}, column: {}", mouse_event.row, mouse_event.column);
-                },
-                _ => {}
-            }
-        },
+    if let crate::app::AppMode::MetaData = app.mode {
+        return Ok(());
+    }
+    match mouse_event.kind {
+        MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => {
+            app.click(mouse_event.column, mouse_event.row);
+        }
+        MouseEventKind::ScrollUp => {
+            app.zoom_in();
+        }
+        MouseEventKind::ScrollDown => {
+            app.zoom_out();
+        }
         _ => {}
-        /*crossterm::event::MouseEventKind::Up(_) => {},
-        crossterm::event::MouseEventKind::Drag(_) => todo!(),
-        crossterm::event::MouseEventKind::Moved => todo!(),
-        crossterm::event::MouseEventKind::ScrollDown => todo!(),
-        crossterm::event::MouseEventKind::ScrollUp => todo!(),*/
-    }*/
+    }
     Ok(())
 }
@@ -1,42 +1,155 @@
 use anyhow::{anyhow, Context};
 use clap::Parser;
-use headjack::app::App;
+use headjack::app::{App, ImageState};
 use headjack::event::{Event, EventHandler};
 use headjack::handler::{handle_key_events, handle_mouse_events};
 use headjack::tui::Tui;
-use headjack::utils::colors::ColorMode;
+use headjack::utils::brain_volume::BrainVolume;
+use headjack::utils::clusters::Connectivity;
+use headjack::utils::color_map_files;
+use headjack::utils::colors::{self, ColorMap, ColorMode};
+use headjack::utils::difference::DiffOp;
+use headjack::utils::graphics::{self, GraphicsProtocol};
+use headjack::utils::labels::LabelTable;
+use headjack::widgets::slice_widget::Renderer;
 use std::io;
 use tui::backend::CrosstermBackend;
 use tui::Terminal;
 
 
-static LONG_ABOUT: &'static str =
+static LONG_ABOUT: &str =
 "headjack - Interactive NIfTI Viewer for the Terminal\n\n\
 Interactive controls:\n\
 \t- Arrow keys / WSAD: Move slice (Post.-Ant. / Left-Right)\n\
 \t\tNavigate metadata\n\
 \t- ZX: Move slice (Inf.-Sup.)\n\
-\t- Tab: Toggle metadata view\n\
+\t- Shift + arrow keys / WSADZX: Move slice by 10 voxels\n\
+\t- Tab: Cycle views (voxel / metadata / clusters / compare)\n\
 \t- C: Toggle color map\n\
+\t- |: Toggle symmetric intensity range (zero in the middle of the color map)\n\
+\t- F: Toggle radiological / neurological convention\n\
+\t- I: Toggle voxel space (array axes i, j, k) / world space\n\
+\t- H / J / K: Rotate slices about the x / y / z axis (Shift: backwards)\n\
+\t- T: Reset rotation\n\
+\t- M: Cycle projections (slice / MIP / MinIP / mean)\n\
+\t- ( / ): Lower / raise the lower display threshold\n\
+\t- { / }: Lower / raise the upper display threshold\n\
+\t- + / - / mouse wheel: Zoom in / out\n\
+\t- Ctrl + arrow keys / ZX: Pan the zoomed view\n\
+\t- 0: Reset zoom and pan\n\
+\t- Mouse click: Move crosshair\n\
+\t- : / G: Go to x, y, z (mm) / v i, j, k (voxel) / label name\n\
+\t- E / Shift+E: Go to the maximum / minimum\n\
+\t- . / ,: Next / previous cluster peak\n\
+\t- B: Cycle region of interest statistics (off / box / sphere / mask)\n\
+\t- < / >: Shrink / grow the region of interest\n\
+\t- Cluster view: arrow keys select a cluster, + / - change the threshold,\n\
+\t\tC cycles 6 / 18 / 26 connectivity\n\
+\t- L: Cycle layouts (row / single panel / 2x2 / column / mosaic)\n\
+\t- O: Next axis in single panel and mosaic layouts\n\
+\t- [ / ]: Fewer / more mosaic slices\n\
+\t- R: Cycle renderers (half blocks / quadrants / sextants / Braille / graphics)\n\
+\t- V: Cycle compare views (grid / flicker / checkerboard)\n\
+\t- Space: Flicker manually\n\
+\t- N / P: Next / previous image\n\
 \t- Q / Esc / Ctrl+C: Quit";
 
 /// headjack - Interactive NIfTI Viewer for the Terminal
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about=LONG_ABOUT)]
 struct Args {
-    /// Image file name (.nii or .nii.gz)
-    #[arg(index = 1)]
-    input: String,
+    /// Image file names (.nii or .nii.gz), opened as tabs
+    #[arg(index = 1, required = true, num_args = 1..)]
+    input: Vec<String>,
 
-    /// ANSI color mode for terminals not supporting true color (24bit).
-    #[arg(short, long, action)]
+    /// Show the voxelwise comparison with this image (resampled onto the grid of each input).
+    #[arg(long, value_name = "FILE")]
+    diff: Option<String>,
+
+    /// Comparison operation used with --diff.
+    #[arg(long, value_enum, default_value_t = DiffOp::Difference)]
+    diff_op: DiffOp,
+
+    /// Color depth of the terminal (detected automatically if omitted).
+    #[arg(long, value_enum, value_name = "MODE")]
+    color: Option<ColorMode>,
+
+    /// ANSI color mode for terminals not supporting true color (24bit), same as --color ansi256.
+    #[arg(short, long, action, conflicts_with = "color")]
     ansi: bool,
-    
+
+    /// Color map of all images: a built-in one (e.g. "viridis" or "coolwarm"),
+    /// one loaded with --cmap-file, or a color map file.
+    #[arg(long, value_name = "NAME")]
+    cmap: Option<String>,
+
+    /// Load a color map (CSV/TSV of RGB stops, FSL .cmap or matplotlib-style JSON),
+    /// cycled with `C` after the built-in ones and named after the file. Can be repeated.
+    #[arg(long, value_name = "FILE")]
+    cmap_file: Vec<String>,
+
+    /// Color of NaN and infinite voxels, a name (e.g. "magenta") or "#rrggbb".
+    #[arg(long, value_name = "COLOR", value_parser = parse_color, default_value = "#ff00ff")]
+    nan_color: [u8; 3],
+
+    /// Center the color map on zero, with a range of ±max|value| (toggled with `|`).
+    #[arg(long, action)]
+    symmetric: bool,
+
+    /// Hide voxels below this value (display threshold, shown transparent).
+    #[arg(long, value_name = "VALUE", allow_negative_numbers = true)]
+    hide_below: Option<f64>,
+
+    /// Hide voxels above this value (display threshold, shown transparent).
+    #[arg(long, value_name = "VALUE", allow_negative_numbers = true)]
+    hide_above: Option<f64>,
+
+    /// Characters used to draw slices.
+    #[arg(short, long, value_enum, default_value_t = Renderer::HalfBlock)]
+    renderer: Renderer,
+
+    /// Render slices as pixel images with a terminal graphics protocol, e.g. `--graphics=kitty`
+    /// (detected automatically if omitted). Falls back to half blocks if unsupported.
+    #[arg(short, long, value_enum, value_name = "PROTOCOL", num_args = 0..=1, require_equals = true)]
+    graphics: Option<Option<GraphicsProtocol>>,
+
+    /// Radiological display convention (patient left on screen right).
+    #[arg(long, action)]
+    radiological: bool,
+
+    /// Display slices along the array axes (i, j, k) instead of the world axes.
+    #[arg(long, action)]
+    voxel_space: bool,
+
+    /// Clusters of voxels above this value are listed in the cluster view and cycled
+    /// with `.` and `,` (half of the maximum of each image by default).
+    #[arg(long, value_name = "VALUE", allow_negative_numbers = true)]
+    cluster_threshold: Option<f64>,
+
+    /// Neighbours of a voxel that belong to the same cluster.
+    #[arg(long, value_enum, default_value = "26")]
+    connectivity: Connectivity,
+
+    /// Mask image (nonzero voxels) used as region of interest, selected with `B`.
+    #[arg(long, value_name = "FILE")]
+    mask: Option<String>,
+
+    /// Names of the values of label images (lines of `index name`, e.g. a FreeSurfer
+    /// color table or BIDS dseg.tsv), to go to a label by name.
+    #[arg(long, value_name = "FILE")]
+    labels: Option<String>,
+
     /// Verbose (debug) output.
     #[arg(short, long, action)]
     verbose: bool,
 }
 
+fn parse_color(s: &str) -> Result<[u8; 3], String> {
+    s.parse::<tui::style::Color>()
+        .map(colors::tui2rgb)
+        .map_err(|_| format!("Unknown color '{}'", s))
+}
+
 fn main() -> anyhow::Result<()> {
     // Read args
     let args = Args::parse();
@@ -44,50 +157,169 @@ fn main() -> anyhow::Result<()> {
     let color_mode = if args.ansi {
         ColorMode::Ansi256
     } else {
-        ColorMode::TrueColor
+        args.color.unwrap_or_else(ColorMode::detect)
+    };
+    if args.verbose {
+        println!("Color mode: {}", color_mode);
+    }
+
+    let graphics = args
+        .graphics
+        .and_then(|protocol| protocol.or_else(GraphicsProtocol::detect));
+    let (renderer, graphics) = match (graphics, graphics::cell_size()) {
+        (Some(protocol), Some((cell_width, cell_height))) => (
+            Renderer::Graphics {
+                cell_width,
+                cell_height,
+            },
+            Some(protocol),
+        ),
+        _ => {
+            if args.verbose && args.graphics.is_some() {
+                println!("No terminal graphics available, falling back to half blocks");
+            }
+            (args.renderer, None)
+        }
+    };
+
+    // Load color maps, a file given as --cmap is selected by its name.
+    let cmap_is_file = args
+        .cmap
+        .as_ref()
+        .is_some_and(|cmap| std::path::Path::new(cmap).is_file());
+    let cmap_files = args
+        .cmap_file
+        .iter()
+        .chain(args.cmap.iter().filter(|cmap| cmap_is_file && !args.cmap_file.contains(cmap)));
+    let color_maps = cmap_files
+        .map(|path| {
+            color_map_files::load(path)
+                .map_err(|e| anyhow!(e))
+                .with_context(|| format!("Failed to load color map '{}'", path))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let cmap_name = match (&args.cmap, cmap_is_file) {
+        (Some(_), true) => color_maps.last().map(|(name, _)| name.clone()),
+        (cmap, _) => cmap.clone(),
+    };
+    colors::register_color_maps(color_maps).map_err(|e| anyhow!(e))?;
+    let color_map = match cmap_name {
+        Some(name) => Some(
+            ColorMap::from_name(&name).ok_or_else(|| anyhow!("Unknown color map '{}'", name))?,
+        ),
+        None => None,
+    };
+
+    // Load images.
+    let diff_volume = match &args.diff {
+        Some(path) => Some(
+            BrainVolume::from_nifti(path)
+                .map_err(|e| anyhow!(e))
+                .with_context(|| format!("Failed to load data '{}'", path))?,
+        ),
+        None => None,
+    };
+    // Files that fail to load are skipped and reported, unless no image is left.
+    let mut images = Vec::new();
+    let mut failed = Vec::new();
+    for path in &args.input {
+        let image = match (&args.diff, &diff_volume) {
+            (Some(diff_path), Some(diff_volume)) => {
+                ImageState::new_difference(args.verbose, path, diff_path, diff_volume, args.diff_op)
+            }
+            _ => ImageState::new(args.verbose, path),
+        };
+        match image {
+            Ok(image) => images.push(image),
+            Err(e) => failed.push(anyhow!(e).context(format!("Failed to load data '{}'", path))),
+        }
+    }
+    if images.is_empty() {
+        return Err(failed.into_iter().next().unwrap());
+    }
+    if args.verbose {
+        for e in &failed {
+            println!("{:#}", e);
+        }
+    }
+    for image in images.iter_mut() {
+        image.display_threshold = (args.hide_below, args.hide_above);
+        if let Some(color_map) = color_map {
+            image.color_map = color_map;
+        }
+        if args.symmetric {
+            image.set_symmetric(true);
+        }
+    }
+
+    let labels = match &args.labels {
+        Some(path) => Some(
+            LabelTable::from_file(path)
+                .map_err(|e| anyhow!(e))
+                .with_context(|| format!("Failed to load labels '{}'", path))?,
+        ),
+        None => None,
+    };
+
+    let mask = match &args.mask {
+        Some(path) => Some(
+            BrainVolume::from_nifti(path)
+                .map_err(|e| anyhow!(e))
+                .with_context(|| format!("Failed to load mask '{}'", path))?,
+        ),
+        None => None,
     };
 
     // Create an application.
-    let mut app = App::new(args.verbose, &args.input, color_mode)
-        .map_err(|e| anyhow!(e))
-        .with_context(|| format!("Failed to load data '{}'", &args.input))?;
+    let mut app = App::new(args.verbose, images, color_mode, renderer);
+    app.radiological = args.radiological;
+    app.nan_color = args.nan_color;
+    app.labels = labels;
+    app.mask = mask;
+    app.cluster_threshold = args.cluster_threshold;
+    app.connectivity = args.connectivity;
+    app.message = failed.first().map(|e| Err(format!("{:#}", e)));
+    app.failed_files = failed.len();
+    if args.voxel_space {
+        app.toggle_space();
+    }
 
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(io::stderr());
     let terminal = Terminal::new(backend)?;
     let events = EventHandler::new(250);
-    let mut tui = Tui::new(terminal, events);
+    let mut tui = Tui::new(terminal, events, graphics);
     tui.init()
         .map_err(|e| anyhow!(e))
-        .with_context(|| format!("Failed to init terminal"))?;
+        .context("Failed to init terminal")?;
 
     // Start the main loop.
     while app.running {
         // Render the user interface.
         tui.draw(&mut app)
             .map_err(|e| anyhow!(e))
-            .with_context(|| format!("Failed to draw"))?;
+            .context("Failed to draw")?;
         // Handle events.
         match tui
             .events
             .next()
             .map_err(|e| anyhow!(e))
-            .with_context(|| format!("Failed to process event"))?
+            .context("Failed to process event")?
         {
             Event::Tick => app.tick(),
             Event::Key(key_event) => handle_key_events(key_event, &mut app)
                 .map_err(|e| anyhow!(e))
-                .with_context(|| format!("Failed to process key event"))?,
+                .context("Failed to process key event")?,
             Event::Mouse(mouse_event) => handle_mouse_events(mouse_event, &mut app)
                 .map_err(|e| anyhow!(e))
-                .with_context(|| format!("Failed to process mouse event"))?,
-            Event::Resize(_, _) => {}
+                .context("Failed to process mouse event")?,
+            Event::Resize(_, _) => tui.invalidate_images(),
         }
     }
 
     // Exit the user interface.
     tui.exit()
         .map_err(|e| anyhow!(e))
-        .with_context(|| format!("Failed to exit the interface"))?;
+        .context("Failed to exit the interface")?;
     Ok(())
 }
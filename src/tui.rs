@@ -1,9 +1,12 @@
 use crate::app::App;
 use crate::event::EventHandler;
 use crate::ui;
+use crate::utils::graphics::{self, GraphicsImage, GraphicsProtocol};
+use crossterm::cursor::MoveTo;
 use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::style::{Print, ResetColor};
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
-use std::io;
+use std::io::{self, Write};
 use std::panic;
 use tui::backend::Backend;
 use tui::Terminal;
@@ -18,12 +21,25 @@ pub struct Tui<B: Backend> {
     terminal: Terminal<B>,
     /// Terminal event handler.
     pub events: EventHandler,
+    /// Protocol used to display pixel images, if any.
+    graphics: Option<GraphicsProtocol>,
+    /// Areas and fingerprints of the pixel images currently on screen.
+    shown_images: Vec<(tui::layout::Rect, u64)>,
 }
 
 impl<B: Backend> Tui<B> {
     /// Constructs a new instance of [`Tui`].
-    pub fn new(terminal: Terminal<B>, events: EventHandler) -> Self {
-        Self { terminal, events }
+    pub fn new(
+        terminal: Terminal<B>,
+        events: EventHandler,
+        graphics: Option<GraphicsProtocol>,
+    ) -> Self {
+        Self {
+            terminal,
+            events,
+            graphics,
+            shown_images: Vec::new(),
+        }
     }
 
     /// Initializes the terminal interface.
@@ -55,9 +71,63 @@ impl<B: Backend> Tui<B> {
         app: &mut App,
     ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
         self.terminal.draw(|frame| ui::render::<B>(app, frame))?;
+        if let Some(protocol) = self.graphics {
+            self.draw_images(&app.slice_output.images, protocol)?;
+        }
         Ok(())
     }
 
+    /// Emits the pixel images of the last frame that are not on screen yet.
+    ///
+    /// The cells below the images are skipped by the terminal buffer,
+    /// so unchanged images stay visible without being sent again.
+    fn draw_images(
+        &mut self,
+        images: &[GraphicsImage],
+        protocol: GraphicsProtocol,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let shown: Vec<_> = images
+            .iter()
+            .map(|image| (image.area, image.fingerprint()))
+            .collect();
+        if shown == self.shown_images {
+            return Ok(());
+        }
+
+        let mut out = io::stderr();
+        for (id, image) in images.iter().enumerate() {
+            if self.shown_images.get(id) == Some(&shown[id]) {
+                continue;
+            }
+            // Sixel and iTerm2 images are drawn over the previous image, which would
+            // remain visible below transparent pixels; text replaces it.
+            if protocol != GraphicsProtocol::Kitty && image.has_transparency() {
+                let blank = " ".repeat(image.area.width as usize);
+                for y in image.area.top()..image.area.bottom() {
+                    crossterm::queue!(out, MoveTo(image.area.x, y), ResetColor, Print(&blank))?;
+                }
+            }
+            crossterm::queue!(out, MoveTo(image.area.x, image.area.y))?;
+            out.write_all(image.encode(protocol, id as u32 + 1).as_bytes())?;
+        }
+        if let GraphicsProtocol::Kitty = protocol {
+            // kitty images are not overwritten by text and must be removed explicitly
+            for id in images.len()..self.shown_images.len() {
+                out.write_all(graphics::kitty_delete(id as u32 + 1).as_bytes())?;
+            }
+        }
+        out.flush()?;
+
+        self.shown_images = shown;
+        Ok(())
+    }
+
+    /// Forces all pixel images to be sent again with the next frame,
+    /// e.g. after the screen was cleared on resize.
+    pub fn invalidate_images(&mut self) {
+        self.shown_images.clear();
+    }
+
     /// Resets the terminal interface.
     ///
     /// This function is also used for the panic hook to revert
@@ -72,6 +142,9 @@ impl<B: Backend> Tui<B> {
     ///
     /// It disables the raw mode and reverts back the terminal properties.
     pub fn exit(&mut self) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(GraphicsProtocol::Kitty) = self.graphics {
+            io::stderr().write_all(graphics::kitty_delete_all().as_bytes())?;
+        }
         Self::reset()?;
         self.terminal.show_cursor()?;
         Ok(())
@@ -1,28 +1,79 @@
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Sparkline},
     Frame,
 };
 
 use crate::{
-    app::App,
+    app::{App, CompareView},
+    utils::roi::{Roi, RoiShape, RoiStats},
     widgets::{
         color_bar::ColorBarWidget,
+        file_tabs::{short_file_labels, FileTabsWidget},
         key_value_list_widget::KeyValueListWidget,
-        slice_widget::{SliceParams, XyzWidget},
+        slice_widget::{CheckerboardPartner, SliceOutput, SliceParams, XyzWidget},
+        table_widget::TableWidget,
         title_bar::TitleBarWidget,
     },
 };
 
-static MODE_TITLES: [&str; 2] = ["Voxel", "Metadata"];
+static MODE_TITLES: [&str; 4] = ["Voxel", "Metadata", "Clusters", "Compare"];
+static CLUSTER_TABLE_HEADER: [&str; 6] = ["#", "Voxels", "Peak", "x", "y", "z"];
+/// Width of the region of interest statistics including their borders.
+const ROI_PANEL_WIDTH: u16 = 30;
+/// Width of the cluster table including its borders.
+const CLUSTER_TABLE_WIDTH: u16 = 44;
 
 /// Renders the user interface widgets.
+///
+/// The placements and pixel images of the slices are collected in [`App::slice_output`].
 pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_>) {
+    let mut output = std::mem::take(&mut app.slice_output);
+    output.clear();
+    render_main(app, frame, &mut output);
+    app.slice_output = output;
+    render_prompt(app, frame);
+}
+
+/// Shows the go-to prompt or the outcome of the last command in place of the color bar.
+fn render_prompt(app: &App, frame: &mut Frame<'_>) {
+    let line = match (&app.prompt, &app.message) {
+        (Some(input), _) => {
+            let mut spans = vec![
+                Span::styled("Go to: ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(input.as_str()),
+                Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+            ];
+            if input.is_empty() {
+                let placeholder = match app.labels {
+                    Some(_) => " x, y, z (mm) / v i, j, k (voxel) / label name",
+                    None => " x, y, z (mm) / v i, j, k (voxel)",
+                };
+                spans.push(Span::styled(placeholder, Style::default().fg(Color::DarkGray)));
+            }
+            Line::from(spans)
+        }
+        (None, Some(Ok(message))) => Line::styled(message.as_str(), Style::default().fg(Color::Cyan)),
+        (None, Some(Err(message))) => Line::styled(message.as_str(), Style::default().fg(Color::Red)),
+        (None, None) => return,
+    };
+    let area = frame.area();
+    let area = Rect::new(area.x, area.bottom().saturating_sub(1), area.width, area.height.min(1));
+    frame.render_widget(Clear, area);
+    frame.render_widget(line, area);
+}
+
+fn render_main(app: &mut App, frame: &mut Frame<'_>, output: &mut SliceOutput) {
+    let tabs_height = if app.images.len() > 1 { 1 } else { 0 };
     let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
                 Constraint::Length(1),
+                Constraint::Length(tabs_height),
                 Constraint::Min(0),
                 Constraint::Length(1),
             ]
@@ -35,40 +86,355 @@ pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_>) {
     let mode_index = match app.mode {
         crate::app::AppMode::Xyz => 0,
         crate::app::AppMode::MetaData => 1,
+        crate::app::AppMode::Clusters => 2,
+        crate::app::AppMode::Compare => 3,
+    };
+    let mode_titles = if app.images.len() > 1 {
+        &MODE_TITLES[..]
+    } else {
+        &MODE_TITLES[..3]
     };
 
+    let warning = match app.failed_files {
+        0 => None,
+        1 => Some("1 file failed to load".to_string()),
+        n => Some(format!("{} files failed to load", n)),
+    };
     frame.render_widget(
-        TitleBarWidget::new(&app.file_path, &MODE_TITLES, mode_index),
+        TitleBarWidget::new(&app.image().file_path, mode_titles, mode_index)
+            .description(app.image().description.as_deref())
+            .warning(warning.as_deref()),
         layout[0],
     );
 
+    let file_paths: Vec<String> = app.images.iter().map(|i| i.file_path.clone()).collect();
+    let file_paths: Vec<&str> = file_paths.iter().map(|p| p.as_str()).collect();
+    if app.images.len() > 1 {
+        frame.render_widget(FileTabsWidget::new(&file_paths, app.image_index), layout[1]);
+    }
+
+    let color_mode = app.color_mode;
+    let renderer = app.renderer;
+    let radiological = app.radiological;
+    let projection = app.projection;
+    let nan_color = app.nan_color;
+    let slice_frame = app.display_frame();
+    let (xyz_layout, single_axis, mosaic_slices) = (app.layout, app.single_axis, app.mosaic_slices);
+    let (zoom, pan) = (app.zoom, app.pan);
+    let mode = app.mode;
+
+    if let crate::app::AppMode::Compare = mode {
+        render_compare(app, frame, output, &file_paths, layout[2], layout[3]);
+        return;
+    }
+
+    let cluster_table = match mode {
+        crate::app::AppMode::Clusters => Some((
+            app.cluster_table(),
+            format!(
+                "Clusters > {:.3} ({} neighbours)",
+                app.cluster_threshold(),
+                app.connectivity.neighbours()
+            ),
+        )),
+        _ => None,
+    };
+
+    let roi_stats = match mode {
+        crate::app::AppMode::Xyz | crate::app::AppMode::Clusters => app.roi_stats(),
+        _ => None,
+    };
+
+    let image = app.image_mut();
+
     frame.render_widget(
         ColorBarWidget::new(
-            app.color_map,
-            app.color_mode,
-            app.intensity_range.0,
-            app.intensity_range.1,
-        ),
-        layout[2],
+            image.color_map,
+            color_mode,
+            image.intensity_range.0,
+            image.intensity_range.1,
+        )
+        .non_finite(image.non_finite, nan_color)
+        .display_threshold(image.display_threshold),
+        layout[3],
     );
 
-    match app.mode {
-        crate::app::AppMode::Xyz => {
+    match mode {
+        crate::app::AppMode::Xyz | crate::app::AppMode::Clusters => {
+            let area = match &cluster_table {
+                Some((rows, title)) => {
+                    let columns = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints([Constraint::Length(CLUSTER_TABLE_WIDTH), Constraint::Min(0)])
+                        .split(layout[2]);
+                    let block = Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .title(title.as_str());
+                    let table_area = block.inner(columns[0]);
+                    frame.render_widget(block, columns[0]);
+                    frame.render_widget(
+                        TableWidget::new(&CLUSTER_TABLE_HEADER, rows, image.peak_index),
+                        table_area,
+                    );
+                    columns[1]
+                }
+                None => layout[2],
+            };
+            let area = match &roi_stats {
+                Some((roi, stats)) => {
+                    let columns = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints([Constraint::Min(0), Constraint::Length(ROI_PANEL_WIDTH)])
+                        .split(area);
+                    let units = crate::utils::brain_volume::xyz_units_str(&image.volume.header);
+                    render_roi(frame, roi, stats, units, columns[1]);
+                    columns[0]
+                }
+                None => area,
+            };
             let slice = SliceParams {
-                position: app.slice_position.clone(),
-                intensity_range: app.intensity_range,
-                color_map: app.color_map,
-                color_mode: app.color_mode,
+                position: image.position_in(&slice_frame),
+                intensity_range: image.intensity_range,
+                display_threshold: image.display_threshold,
+                color_map: image.color_map,
+                color_mode,
+                renderer,
+                radiological,
+                projection,
+                frame: slice_frame,
+                nan_color,
             };
+            let info = image.crosshair_info();
+            let bounds = image.view_bounds(zoom, &pan, &slice_frame);
             frame.render_widget(
-                XyzWidget::new(&app.volume, &mut app.image_cache, &slice),
-                layout[1],
+                XyzWidget::new(&image.volume, &mut image.image_cache, &slice)
+                    .output(output)
+                    .layout(xyz_layout, single_axis)
+                    .mosaic(mosaic_slices)
+                    .bounds(bounds)
+                    .info(&info),
+                area,
             );
         }
         crate::app::AppMode::MetaData => {
+            image.update_statistics();
+            frame.render_widget(
+                KeyValueListWidget::new(&image.metadata, image.metadata_index),
+                layout[2],
+            );
+        }
+        crate::app::AppMode::Compare => {}
+    }
+}
+
+/// Renders the statistics of a region of interest and, for 4D data, its mean time series.
+fn render_roi(frame: &mut Frame<'_>, roi: &Roi, stats: &RoiStats, units: &str, area: Rect) {
+    let title = match roi.shape {
+        RoiShape::Mask => "ROI: Mask".to_owned(),
+        shape => format!("ROI: {} r = {:.1} {}", shape, roi.radius, units),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(title);
+    let inner_area = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        // one spare row, the key value list marks its last row as truncated
+        .constraints([Constraint::Length(9), Constraint::Min(0)])
+        .split(inner_area);
+    let items = stats.key_value_list(units);
+    frame.render_widget(KeyValueListWidget::new(&items, 0), rows[0]);
+
+    if let Some(series) = &stats.time_series {
+        let block = Block::default().borders(Borders::TOP).title("Mean over time");
+        let width = block.inner(rows[1]).width as usize;
+        let data = sparkline_data(series, width);
+        frame.render_widget(
+            Sparkline::default()
+                .block(block)
+                .data(&data)
+                .style(Style::default().fg(Color::Cyan)),
+            rows[1],
+        );
+    }
+}
+
+/// Scales `series` to the range 1 to 100 and averages neighbouring values to fit `width`.
+///
+/// NaN and Inf are left out, bins without finite values are drawn empty.
+fn sparkline_data(series: &[f64], width: usize) -> Vec<u64> {
+    let bins = series.len().min(width).max(1);
+    let binned: Vec<f64> = (0..bins)
+        .map(|b| {
+            let values = &series[b * series.len() / bins..(b + 1) * series.len() / bins];
+            let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+            finite.iter().sum::<f64>() / finite.len() as f64
+        })
+        .collect();
+    // f64::min and f64::max ignore NaN
+    let min = binned.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = binned.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = if max > min { max - min } else { 1. };
+    binned
+        .iter()
+        .map(|v| if v.is_nan() { 0 } else { 1 + ((v - min) / range * 99.) as u64 })
+        .collect()
+}
+
+/// Renders the comparison of the opened images.
+fn render_compare(
+    app: &mut App,
+    frame: &mut Frame<'_>,
+    output: &mut SliceOutput,
+    file_paths: &[&str],
+    area: Rect,
+    color_bar_area: Rect,
+) {
+    let color_mode = app.color_mode;
+    let renderer = app.renderer;
+    let radiological = app.radiological;
+    let projection = app.projection;
+    let nan_color = app.nan_color;
+    let slice_frame = app.display_frame();
+    let (xyz_layout, single_axis, mosaic_slices) = (app.layout, app.single_axis, app.mosaic_slices);
+    let (zoom, pan) = (app.zoom, app.pan);
+    let labels = short_file_labels(file_paths);
+    let title_style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+    let displayed_index = match app.compare_view {
+        CompareView::Flicker if app.flicker_phase => app.partner_index(),
+        _ => app.image_index,
+    };
+    let image = &app.images[displayed_index];
+    frame.render_widget(
+        ColorBarWidget::new(
+            image.color_map,
+            color_mode,
+            image.intensity_range.0,
+            image.intensity_range.1,
+        )
+        .non_finite(image.non_finite, nan_color)
+        .display_threshold(image.display_threshold),
+        color_bar_area,
+    );
+
+    match app.compare_view {
+        CompareView::Grid => {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(vec![Constraint::Ratio(1, app.images.len() as u32); app.images.len()])
+                .split(area);
+
+            for (idx, image) in app.images.iter_mut().enumerate() {
+                let slice = SliceParams {
+                    position: image.position_in(&slice_frame),
+                    intensity_range: image.intensity_range,
+                    display_threshold: image.display_threshold,
+                    color_map: image.color_map,
+                    color_mode,
+                    renderer,
+                    radiological,
+                    projection,
+                    frame: slice_frame,
+                    nan_color,
+                };
+                let bounds = image.view_bounds(zoom, &pan, &slice_frame);
+                let style = if idx == app.image_index {
+                    title_style
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                frame.render_widget(
+                    XyzWidget::new(&image.volume, &mut image.image_cache, &slice)
+                        .output(output)
+                        .layout(xyz_layout, single_axis)
+                        .mosaic(mosaic_slices)
+                        .bounds(bounds)
+                        .block(Block::default().title(Span::styled(labels[idx].as_str(), style))),
+                    rows[idx],
+                );
+            }
+        }
+        CompareView::Flicker => {
+            // Both images are sampled on the grid of the current image so that they overlap.
+            let bounds = app.image().view_bounds(zoom, &pan, &slice_frame);
+            let title = format!(
+                "Flicker{}: {}",
+                if app.flicker_running { "" } else { " (paused)" },
+                labels[displayed_index]
+            );
+            let image = &mut app.images[displayed_index];
+            let slice = SliceParams {
+                position: image.position_in(&slice_frame),
+                intensity_range: image.intensity_range,
+                display_threshold: image.display_threshold,
+                color_map: image.color_map,
+                color_mode,
+                renderer,
+                radiological,
+                projection,
+                frame: slice_frame,
+                nan_color,
+            };
+            frame.render_widget(
+                XyzWidget::new(&image.volume, &mut image.image_cache, &slice)
+                    .output(output)
+                    .layout(xyz_layout, single_axis)
+                    .mosaic(mosaic_slices)
+                    .bounds(bounds)
+                    .block(Block::default().title(Span::styled(title, title_style))),
+                area,
+            );
+        }
+        CompareView::Checkerboard => {
+            let title = format!(
+                "Checkerboard: {} / {}",
+                labels[app.image_index],
+                labels[app.partner_index()]
+            );
+            let (image, partner) = app.image_and_partner_mut();
+            let bounds = image.view_bounds(zoom, &pan, &slice_frame);
+            let slice = SliceParams {
+                position: image.position_in(&slice_frame),
+                intensity_range: image.intensity_range,
+                display_threshold: image.display_threshold,
+                color_map: image.color_map,
+                color_mode,
+                renderer,
+                radiological,
+                projection,
+                frame: slice_frame,
+                nan_color,
+            };
+            let partner_slice = SliceParams {
+                position: partner.position_in(&slice_frame),
+                intensity_range: partner.intensity_range,
+                display_threshold: partner.display_threshold,
+                color_map: partner.color_map,
+                color_mode,
+                renderer,
+                radiological,
+                projection,
+                frame: slice_frame,
+                nan_color,
+            };
             frame.render_widget(
-                KeyValueListWidget::new(&app.metadata, app.metadata_index),
-                layout[1],
+                XyzWidget::new(&image.volume, &mut image.image_cache, &slice)
+                    .output(output)
+                    .layout(xyz_layout, single_axis)
+                    .mosaic(mosaic_slices)
+                    .bounds(bounds)
+                    .checkerboard(CheckerboardPartner {
+                        volume: &partner.volume,
+                        image_cache: &mut partner.image_cache,
+                        slice: &partner_slice,
+                    })
+                    .block(Block::default().title(Span::styled(title, title_style))),
+                area,
             );
         }
     }
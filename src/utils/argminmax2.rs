@@ -12,7 +12,7 @@ where
         let con = self.as_standard_layout();
         let contiguous = con.as_slice().unwrap();
         let (pmin, pmax) = contiguous.argminmax();
-        return (contiguous[pmin], contiguous[pmax]);
+        (contiguous[pmin], contiguous[pmax])
     }
 }
 impl<D> MinMax2<f64> for ndarray::Array<f64, D>
@@ -23,7 +23,7 @@ where
         let con = self.as_standard_layout();
         let contiguous = con.as_slice().unwrap();
         let (pmin, pmax) = contiguous.argminmax();
-        return (contiguous[pmin], contiguous[pmax]);
+        (contiguous[pmin], contiguous[pmax])
     }
 }
 impl<D> MinMax2<u16> for ndarray::Array<u16, D>
@@ -34,7 +34,7 @@ where
         let con = self.as_standard_layout();
         let contiguous = con.as_slice().unwrap();
         let (pmin, pmax) = contiguous.argminmax();
-        return (contiguous[pmin], contiguous[pmax]);
+        (contiguous[pmin], contiguous[pmax])
     }
 }
 impl<D> MinMax2<u32> for ndarray::Array<u32, D>
@@ -45,7 +45,7 @@ where
         let con = self.as_standard_layout();
         let contiguous = con.as_slice().unwrap();
         let (pmin, pmax) = contiguous.argminmax();
-        return (contiguous[pmin], contiguous[pmax]);
+        (contiguous[pmin], contiguous[pmax])
     }
 }
 impl<D> MinMax2<u64> for ndarray::Array<u64, D>
@@ -56,6 +56,32 @@ where
         let con = self.as_standard_layout();
         let contiguous = con.as_slice().unwrap();
         let (pmin, pmax) = contiguous.argminmax();
-        return (contiguous[pmin], contiguous[pmax]);
+        (contiguous[pmin], contiguous[pmax])
     }
 }
+
+/// Minimum and maximum of the finite values, `None` if there are none.
+pub fn finite_minmax<'a>(values: impl IntoIterator<Item = &'a f64>) -> Option<(f64, f64)> {
+    values
+        .into_iter()
+        .filter(|v| v.is_finite())
+        .fold(None, |range, &v| match range {
+            Some((min, max)) => Some((f64::min(min, v), f64::max(max, v))),
+            None => Some((v, v)),
+        })
+}
+
+/// Indices (i, j, k) of the minimum and maximum of the finite values of a
+/// 3D array, `None` if there are none.
+pub fn argminmax_3d(arr: &ndarray::ArrayView3<f64>) -> Option<([usize; 3], [usize; 3])> {
+    arr.indexed_iter()
+        .filter(|(_, v)| v.is_finite())
+        .fold(None, |extrema, ((i, j, k), &v)| match extrema {
+            Some(((min, min_index), (max, max_index))) => Some((
+                if v < min { (v, [i, j, k]) } else { (min, min_index) },
+                if v > max { (v, [i, j, k]) } else { (max, max_index) },
+            )),
+            None => Some(((v, [i, j, k]), (v, [i, j, k]))),
+        })
+        .map(|((_, min_index), (_, max_index))| (min_index, max_index))
+}
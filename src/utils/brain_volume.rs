@@ -1,10 +1,11 @@
 use std::error::Error;
+use std::sync::Arc;
 
 use ndarray::prelude::*;
 use nifti::{IntoNdArray, NiftiObject, NiftiHeader};
 use crate::utils::sampling;
 
-use crate::utils::argminmax2::MinMax2;
+use crate::utils::argminmax2;
 
 pub type BrainMetaData = nifti::header::NiftiHeader;
 
@@ -13,7 +14,8 @@ pub type BrainMetaData = nifti::header::NiftiHeader;
 
 #[derive(Debug)]
 pub struct BrainVolume {
-    pub arr: ArrayD<f64>,
+    /// Shared with background computations such as the whole-image statistics.
+    pub arr: Arc<ArrayD<f64>>,
     pub local_bounds: sampling::Cube,
     pub world_bounds: sampling::Cube,
     pub intensity_range: (f64, f64),
@@ -35,69 +37,246 @@ impl BrainVolume {
     pub fn from_nifti(path_nifti: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
         let nif = nifti::ReaderOptions::new().read_file(path_nifti)?;
         let header = nif.header().to_owned();
+
+        let arr = { nif.into_volume().into_ndarray::<f64>()?.reversed_axes() };
+
+        Ok(Self::from_array(arr, header))
+    }
+
+    /// Constructs a volume from data with the geometry described by `header`.
+    pub fn from_array(arr: ArrayD<f64>, header: BrainMetaData) -> Self {
         let affine = header_get_affine(&header);
         let affine_inv = sampling::invert_affine_transform(&affine.view());
 
-        let arr = { nif.into_volume().into_ndarray::<f64>()?.reversed_axes() };
-        let arr_first_3d_slice = array_view_3d(&arr).to_owned();
+        let arr_first_3d_slice = array_view_3d(&arr);
 
         let local_bounds = sampling::bounding_cube_from_shape_3d(arr_first_3d_slice.shape());
         let local_array_corners = local_bounds.corner_coords();
         let world_array_corners = sampling::coords_apply_affine_transform(&local_array_corners.view(), &affine.view());
         let world_bounds = sampling::bounding_cube_from_coords(&world_array_corners.view());
 
-        let intensity_range = arr_first_3d_slice.minmax2();
+        // NaN and Inf are drawn in a separate color
+        let intensity_range = argminmax2::finite_minmax(arr_first_3d_slice).unwrap_or((0., 1.));
 
-        Ok(Self {
-            arr,
+        Self {
+            arr: Arc::new(arr),
             local_bounds,
             world_bounds,
             intensity_range,
             affine,
             affine_inv,
             header,
-        })
+        }
     }
 
-    pub fn array_view_3d(&self) -> ArrayView3<f64> {
+    pub fn array_view_3d(&self) -> ArrayView3<'_, f64> {
         array_view_3d(&self.arr)
     }
 
-    pub fn world_slice(&self, axis: sampling::SliceAxis, depth: f64, default_value: f64, resolution: (usize, usize)) -> Array2<f64> {
+    /// Samples a slice of the world space region `bounds`.
+    ///
+    /// `bounds` is usually `self.world_bounds`, but may be the bounds of another
+    /// volume so that both are sampled on the same grid. Oblique slices are
+    /// sampled by rotating the plane with `transform`, see [`sampling::slice_cube_3d_coords`].
+    pub fn world_slice(&self, axis: sampling::SliceAxis, depth: f64, default_value: f64, resolution: (usize, usize), bounds: sampling::Cube, transform: Option<&ArrayView2<f64>>) -> Array2<f64> {
 
-        let world_sample_coords = sampling::slice_cube_3d_coords(axis, resolution.0, resolution.1, depth, self.world_bounds);
+        let world_sample_coords = sampling::slice_cube_3d_coords(axis, resolution.0, resolution.1, depth, bounds, transform);
         let local_sample_coords = sampling::coords_apply_affine_transform(&world_sample_coords.view(), &self.affine_inv.view());
 
         let arr_view_3d = self.array_view_3d();
         
         let out_arr_flat = sampling::map_coordinates_3d(&arr_view_3d, &local_sample_coords.view(), default_value);
-        let out_arr_2d = out_arr_flat.into_shape_with_order((resolution.1, resolution.0)).unwrap().reversed_axes();
 
-        out_arr_2d
+        out_arr_flat.into_shape_with_order((resolution.1, resolution.0)).unwrap().reversed_axes()
     }
 
-    pub fn local_slice(&self, axis: sampling::SliceAxis, depth: f64, default_value: f64, resolution: (usize, usize)) -> Array2<f64> {
+    /// Resamples the first 3D volume onto the voxel grid of `reference`.
+    ///
+    /// Voxels outside of this volume are set to `default_value`.
+    pub fn resample_like(&self, reference: &BrainVolume, default_value: f64) -> Array3<f64> {
+        let shape = reference.array_view_3d().dim();
+        let mut reference_coords = Array2::<f64>::ones((4, shape.0 * shape.1 * shape.2));
+        for (idx, (i, j, k)) in ndarray::indices(shape).into_iter().enumerate() {
+            reference_coords[[0, idx]] = i as f64;
+            reference_coords[[1, idx]] = j as f64;
+            reference_coords[[2, idx]] = k as f64;
+        }
+        let world_coords = sampling::coords_apply_affine_transform(&reference_coords.view(), &reference.affine.view());
+        let local_coords = sampling::coords_apply_affine_transform(&world_coords.view(), &self.affine_inv.view());
+
+        let out_arr_flat = sampling::map_coordinates_3d(&self.array_view_3d(), &local_coords.view(), default_value);
 
-        let world_sample_coords = sampling::slice_cube_3d_coords(axis, resolution.0, resolution.1, depth, self.local_bounds);
+        out_arr_flat.into_shape_with_order(shape).unwrap()
+    }
+
+    /// Continuous voxel coordinates (k, j, i) of a world position (z, y, x).
+    pub fn world_to_voxel(&self, position: &[f64]) -> [f64; 3] {
+        let world = ndarray::array![[position[0]], [position[1]], [position[2]], [1.]];
+        let local = self.affine_inv.dot(&world);
+        [local[[0, 0]], local[[1, 0]], local[[2, 0]]]
+    }
+
+    /// Index of the voxel nearest to a world position, if inside the array.
+    pub fn voxel_index(&self, position: &[f64]) -> Option<[usize; 3]> {
+        let local = self.world_to_voxel(position);
+        let (di, dj, dk) = self.array_view_3d().dim();
+        let index = |v: f64, d: usize| {
+            let v = v.round();
+            (v >= 0. && v < d as f64).then_some(v as usize)
+        };
+        Some([index(local[0], di)?, index(local[1], dj)?, index(local[2], dk)?])
+    }
+
+    /// Value of the voxel nearest to a world position, if inside the array.
+    pub fn value_at(&self, position: &[f64]) -> Option<f64> {
+        self.voxel_index(position).map(|[i, j, k]| self.array_view_3d()[[i, j, k]])
+    }
+
+    /// Samples a slice of the voxel space region `bounds` along the array axes.
+    ///
+    /// `bounds` is usually `self.local_bounds`.
+    pub fn local_slice(&self, axis: sampling::SliceAxis, depth: f64, default_value: f64, resolution: (usize, usize), bounds: sampling::Cube) -> Array2<f64> {
+
+        let local_sample_coords = sampling::slice_cube_3d_coords(axis, resolution.0, resolution.1, depth, bounds, None);
 
         let arr_view_3d = self.array_view_3d();
         
-        let out_arr_flat = sampling::map_coordinates_3d(&arr_view_3d, &world_sample_coords.view(), default_value);
-        let out_arr_2d = out_arr_flat.into_shape_with_order(resolution).unwrap();
+        let out_arr_flat = sampling::map_coordinates_3d(&arr_view_3d, &local_sample_coords.view(), default_value);
+
+        out_arr_flat.into_shape_with_order((resolution.1, resolution.0)).unwrap().reversed_axes()
+    }
+
+    /// Voxel of the region with the given value closest to its centroid, if any.
+    ///
+    /// Unlike the centroid itself, the voxel is always inside the region.
+    pub fn region_center(&self, value: f64) -> Option<[usize; 3]> {
+        let arr = self.array_view_3d();
+        let voxels: Vec<[usize; 3]> = arr
+            .indexed_iter()
+            .filter(|(_, &v)| v == value)
+            .map(|((i, j, k), _)| [i, j, k])
+            .collect();
+        let n = voxels.len() as f64;
+        let centroid = [0, 1, 2].map(|axis| voxels.iter().map(|v| v[axis] as f64).sum::<f64>() / n);
+        let distance = |v: &[usize; 3]| (0..3).map(|axis| (v[axis] as f64 - centroid[axis]).powi(2)).sum::<f64>();
+        voxels.into_iter().min_by(|a, b| distance(a).total_cmp(&distance(b)))
+    }
+
+    /// World position (z, y, x) of continuous voxel coordinates (k, j, i).
+    pub fn voxel_to_world(&self, voxel: &[f64]) -> [f64; 3] {
+        let local = ndarray::array![[voxel[0]], [voxel[1]], [voxel[2]], [1.]];
+        let world = self.affine.dot(&local);
+        [world[[0, 0]], world[[1, 0]], world[[2, 0]]]
+    }
+
+    /// Projects the region `bounds` through the whole volume along `axis`, combining
+    /// the samples along the projection direction with `projection`.
+    ///
+    /// Rays are sampled about once per voxel; pixels whose rays miss the volume
+    /// are set to `default_value`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn projection(&self, projection: sampling::Projection, space: sampling::SliceSpace, axis: sampling::SliceAxis, default_value: f64, resolution: (usize, usize), bounds: sampling::Cube, transform: Option<&ArrayView2<f64>>) -> Array2<f64> {
+
+        // extent of the volume along the (rotated) projection direction
+        let mut corners = self.bounds(space).corner_coords();
+        if let Some(transform) = transform {
+            let inverse = sampling::invert_affine_transform(transform);
+            corners = sampling::coords_apply_affine_transform(&corners.view(), &inverse.view());
+        }
+        let extent = sampling::bounding_cube_from_coords(&corners.view());
+        let (min, max) = (extent.min()[axis.index()], extent.max()[axis.index()]);
+
+        let step = match space {
+            sampling::SliceSpace::World => self.voxel_size(),
+            sampling::SliceSpace::Voxel => 1.,
+        };
+        let steps = ((max - min) / step).ceil() as usize + 1;
+
+        let mut combined = Array2::<f64>::from_elem((resolution.0, resolution.1), f64::NAN);
+        let mut counts = Array2::<f64>::zeros((resolution.0, resolution.1));
+        for i in 0..steps {
+            let depth = (min + i as f64 * step).min(max);
+            let slice = match space {
+                sampling::SliceSpace::World => self.world_slice(axis, depth, f64::NAN, resolution, bounds, transform),
+                sampling::SliceSpace::Voxel => self.local_slice(axis, depth, f64::NAN, resolution, bounds),
+            };
+            ndarray::Zip::from(&mut combined).and(&mut counts).and(&slice).for_each(|acc, count, &value| {
+                if value.is_nan() {
+                    return;
+                }
+                *acc = match projection {
+                    // f64::max and f64::min ignore NaN
+                    sampling::Projection::Max => acc.max(value),
+                    sampling::Projection::Min => acc.min(value),
+                    sampling::Projection::Slice | sampling::Projection::Mean => {
+                        if acc.is_nan() { value } else { *acc + value }
+                    }
+                };
+                *count += 1.;
+            });
+        }
+
+        ndarray::Zip::from(&mut combined).and(&counts).for_each(|acc, &count| {
+            *acc = if count == 0. {
+                default_value
+            } else if projection == sampling::Projection::Mean {
+                *acc / count
+            } else {
+                *acc
+            };
+        });
+        combined
+    }
+
+    /// Voxel edge length along the z, y and x axis, taken from the array
+    /// axis closest to each world axis.
+    pub fn world_spacing(&self) -> [f64; 3] {
+        let norms = self.array_spacing();
+        [0, 1, 2].map(|world_axis| norms[self.closest_array_axis(world_axis)])
+    }
+
+    /// Voxel edge length along the k, j and i axis.
+    fn array_spacing(&self) -> [f64; 3] {
+        [0, 1, 2].map(|axis| self.affine.slice(s![..3, axis]).mapv(|v| v * v).sum().sqrt())
+    }
+
+    /// Array axis with the smallest angle to a world axis.
+    fn closest_array_axis(&self, world_axis: usize) -> usize {
+        let norms = self.array_spacing();
+        // cosine of the angle between array and world axis
+        let alignment = |axis: usize| (self.affine[[world_axis, axis]] / norms[axis]).abs();
+        (0..3)
+            .max_by(|&a, &b| alignment(a).total_cmp(&alignment(b)))
+            .unwrap()
+    }
+
+    /// Smallest voxel edge length in world units.
+    pub fn voxel_size(&self) -> f64 {
+        self.world_spacing().into_iter().fold(f64::INFINITY, f64::min)
+    }
 
-        out_arr_2d
+    /// Bounding box of the volume in world or voxel coordinates.
+    pub fn bounds(&self, space: sampling::SliceSpace) -> sampling::Cube {
+        match space {
+            sampling::SliceSpace::World => self.world_bounds,
+            sampling::SliceSpace::Voxel => self.local_bounds,
+        }
     }
     
 }
 
+/// Affine from the array axes (k, j, i) to the world axes (z, y, x).
+///
+/// The array axes are reversed on load, so the first three rows and columns
+/// of the NIfTI affine are reversed as well.
 fn header_get_affine(header: &NiftiHeader) -> ndarray::Array2<f64> {
     let x = header.srow_x;
     let y = header.srow_y;
     let z = header.srow_z;
     ndarray::array![
-        [x[0], x[1], x[2], x[3]],
-        [y[0], y[1], y[2], y[3]],
-        [z[0], z[1], z[2], z[3]],
+        [z[2], z[1], z[0], z[3]],
+        [y[2], y[1], y[0], y[3]],
+        [x[2], x[1], x[0], x[3]],
         [0., 0., 0., 1.]
     ].mapv(|elem| elem as f64)
 }
@@ -0,0 +1,186 @@
+use ndarray::{Array3, ArrayView3};
+
+/// Which neighbouring voxels are connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Connectivity {
+    /// 6 neighbours sharing a face.
+    #[value(name = "6")]
+    Faces,
+    /// 18 neighbours sharing a face or an edge.
+    #[value(name = "18")]
+    Edges,
+    /// 26 neighbours sharing a face, an edge or a corner.
+    #[value(name = "26")]
+    Corners,
+}
+
+impl Connectivity {
+    pub fn next(&self) -> Self {
+        match self {
+            Connectivity::Faces => Connectivity::Edges,
+            Connectivity::Edges => Connectivity::Corners,
+            Connectivity::Corners => Connectivity::Faces,
+        }
+    }
+
+    /// Number of neighbours of a voxel.
+    pub fn neighbours(&self) -> usize {
+        match self {
+            Connectivity::Faces => 6,
+            Connectivity::Edges => 18,
+            Connectivity::Corners => 26,
+        }
+    }
+
+    /// Largest number of axes along which neighbours are offset.
+    fn max_offset_axes(&self) -> usize {
+        match self {
+            Connectivity::Faces => 1,
+            Connectivity::Edges => 2,
+            Connectivity::Corners => 3,
+        }
+    }
+}
+
+/// Connected region of voxels above a threshold.
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    /// Voxel index of the maximum.
+    pub peak: [usize; 3],
+    pub peak_value: f64,
+    /// Number of voxels.
+    pub size: usize,
+}
+
+/// Finds the clusters of connected finite voxels above `threshold`, ordered by decreasing peak value.
+pub fn find_clusters(
+    arr: &ArrayView3<f64>,
+    threshold: f64,
+    connectivity: Connectivity,
+) -> Vec<Cluster> {
+    let shape = arr.dim();
+    let mut visited = Array3::<bool>::from_elem(shape, false);
+    let mut clusters = Vec::new();
+    let mut stack = Vec::new();
+    // NaN and Inf belong to no cluster
+    let above = |value: f64| value.is_finite() && value > threshold;
+
+    for ((i, j, k), &value) in arr.indexed_iter() {
+        if visited[[i, j, k]] || !above(value) {
+            continue;
+        }
+        visited[[i, j, k]] = true;
+        stack.push([i, j, k]);
+        let mut cluster = Cluster {
+            peak: [i, j, k],
+            peak_value: value,
+            size: 0,
+        };
+
+        // flood fill
+        while let Some(voxel) = stack.pop() {
+            cluster.size += 1;
+            let value = arr[voxel];
+            if value > cluster.peak_value {
+                cluster.peak = voxel;
+                cluster.peak_value = value;
+            }
+            for neighbour in neighbours(voxel, shape, connectivity) {
+                if !visited[neighbour] && above(arr[neighbour]) {
+                    visited[neighbour] = true;
+                    stack.push(neighbour);
+                }
+            }
+        }
+        clusters.push(cluster);
+    }
+
+    clusters.sort_by(|a, b| b.peak_value.total_cmp(&a.peak_value));
+    clusters
+}
+
+/// The voxels connected to `voxel` that are inside the array.
+fn neighbours(
+    voxel: [usize; 3],
+    shape: (usize, usize, usize),
+    connectivity: Connectivity,
+) -> impl Iterator<Item = [usize; 3]> {
+    let shape = [shape.0, shape.1, shape.2];
+    // offsets are shifted by one to stay unsigned
+    (0..27)
+        .map(|n| [n / 9, n / 3 % 3, n % 3])
+        .filter(move |offset| {
+            let axes = offset.iter().filter(|&&o| o != 1).count();
+            axes > 0 && axes <= connectivity.max_offset_axes()
+        })
+        .filter_map(move |offset| {
+            let mut neighbour = [0; 3];
+            for axis in 0..3 {
+                neighbour[axis] = (voxel[axis] + offset[axis]).checked_sub(1)?;
+                if neighbour[axis] >= shape[axis] {
+                    return None;
+                }
+            }
+            Some(neighbour)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    /// Voxels touching the first one by a face, an edge and a corner.
+    fn touching_voxels() -> Array3<f64> {
+        let mut arr = Array3::zeros((4, 4, 4));
+        arr[[1, 1, 1]] = 5.;
+        arr[[1, 1, 2]] = 4.;
+        arr[[1, 2, 3]] = 3.;
+        arr[[2, 3, 2]] = 2.;
+        arr
+    }
+
+    #[test]
+    fn connectivity() {
+        let arr = touching_voxels();
+        let count = |connectivity| find_clusters(&arr.view(), 0., connectivity).len();
+        assert_eq!(count(Connectivity::Faces), 3);
+        assert_eq!(count(Connectivity::Edges), 2);
+        assert_eq!(count(Connectivity::Corners), 1);
+    }
+
+    #[test]
+    fn size_peak_and_order() {
+        let mut arr = touching_voxels();
+        // a separate cluster with a higher peak
+        arr[[3, 0, 3]] = 6.;
+        arr[[3, 1, 3]] = 1.;
+        let clusters = find_clusters(&arr.view(), 0.5, Connectivity::Faces);
+        let summary: Vec<_> = clusters
+            .iter()
+            .map(|c| (c.size, c.peak, c.peak_value))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (2, [3, 0, 3], 6.),
+                (2, [1, 1, 1], 5.),
+                (1, [1, 2, 3], 3.),
+                (1, [2, 3, 2], 2.),
+            ]
+        );
+    }
+
+    #[test]
+    fn threshold_and_non_finite_values() {
+        let mut arr = touching_voxels();
+        arr[[0, 0, 0]] = f64::NAN;
+        arr[[0, 0, 1]] = f64::INFINITY;
+        // the NaN and Inf voxels neither form clusters nor connect the others
+        arr[[0, 1, 1]] = f64::INFINITY;
+        // the voxel touching by a corner is below the threshold
+        let clusters = find_clusters(&arr.view(), 2.5, Connectivity::Corners);
+        let summary: Vec<_> = clusters.iter().map(|c| (c.size, c.peak_value)).collect();
+        assert_eq!(summary, vec![(3, 5.)]);
+    }
+}
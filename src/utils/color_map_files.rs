@@ -0,0 +1,221 @@
+use std::error::Error;
+
+/// Evenly spaced RGB colors of a color map.
+pub type Stops = Vec<[u8; 3]>;
+
+/// Number of colors sampled from matplotlib segment data.
+const SEGMENT_SAMPLES: usize = 256;
+
+/// Reads a color map, named after the file unless the file names it.
+///
+/// Supported are tables of RGB stops (CSV, TSV or whitespace separated like FSL `.cmap`
+/// files, with values from 0 to 1 or 0 to 255) and JSON files with a list of colors,
+/// an object with `name` and `colors`, or matplotlib segment data (`red`, `green`, `blue`).
+/// Stops are evenly spaced.
+pub fn load(path: &str) -> Result<(String, Stops), Box<dyn Error + Send + Sync>> {
+    let text = std::fs::read_to_string(path)?;
+    let (name, stops) = if path.to_lowercase().ends_with(".json") {
+        parse_json(&text)?
+    } else {
+        (None, parse_table(&text)?)
+    };
+    if stops.len() < 2 {
+        return Err(format!("Color map '{}' needs at least two colors", path).into());
+    }
+    let name = name.unwrap_or_else(|| {
+        std::path::Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_owned())
+    });
+    Ok((name, stops))
+}
+
+/// Parses one color per line, the first three numbers are red, green and blue.
+///
+/// The first line may be a header, lines starting with `#` are skipped and further columns
+/// (e.g. alpha) are ignored.
+pub fn parse_table(text: &str) -> Result<Stops, Box<dyn Error + Send + Sync>> {
+    let mut rows = Vec::new();
+    let mut first = true;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let header_allowed = std::mem::replace(&mut first, false);
+        let values: Result<Vec<f64>, _> = line
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .filter(|value| !value.is_empty())
+            .map(str::parse::<f64>)
+            .collect();
+        match values {
+            Ok(values) if values.len() >= 3 => rows.push([values[0], values[1], values[2]]),
+            // column names
+            Err(_) if header_allowed => continue,
+            _ => return Err(format!("Expected red, green and blue in line {}", number + 1).into()),
+        }
+    }
+    Ok(to_rgb(&rows))
+}
+
+/// Parses a JSON list of colors (`[r, g, b]` or `"#rrggbb"`), an object with
+/// `colors` and optional `name`, or matplotlib segment data.
+pub fn parse_json(text: &str) -> Result<(Option<String>, Stops), Box<dyn Error + Send + Sync>> {
+    let json: serde_json::Value = serde_json::from_str(text)?;
+    let name = json.get("name").and_then(|name| name.as_str()).map(str::to_owned);
+    let colors = match (&json, json.get("colors")) {
+        (serde_json::Value::Array(colors), _) | (_, Some(serde_json::Value::Array(colors))) => {
+            parse_json_colors(colors)?
+        }
+        _ => parse_segment_data(&json)?,
+    };
+    Ok((name, colors))
+}
+
+fn parse_json_colors(colors: &[serde_json::Value]) -> Result<Stops, Box<dyn Error + Send + Sync>> {
+    let mut rows = Vec::new();
+    let mut hex = Vec::new();
+    for color in colors {
+        match color {
+            serde_json::Value::String(s) => hex.push(parse_hex(s)?),
+            serde_json::Value::Array(values) => {
+                let values: Option<Vec<f64>> = values.iter().map(|v| v.as_f64()).collect();
+                match values {
+                    Some(values) if values.len() >= 3 => rows.push([values[0], values[1], values[2]]),
+                    _ => return Err(format!("Invalid color {}", color).into()),
+                }
+            }
+            _ => return Err(format!("Invalid color {}", color).into()),
+        }
+    }
+    match (rows.is_empty(), hex.is_empty()) {
+        (true, _) => Ok(hex),
+        (false, true) => Ok(to_rgb(&rows)),
+        (false, false) => Err("Colors have to be either all RGB lists or all hex strings".into()),
+    }
+}
+
+fn parse_hex(s: &str) -> Result<[u8; 3], Box<dyn Error + Send + Sync>> {
+    let digits = s.trim_start_matches('#');
+    let channel = |i: usize| {
+        digits
+            .get(2 * i..2 * i + 2)
+            .and_then(|d| u8::from_str_radix(d, 16).ok())
+    };
+    match (digits.len(), channel(0), channel(1), channel(2)) {
+        (6, Some(r), Some(g), Some(b)) => Ok([r, g, b]),
+        _ => Err(format!("Invalid color '{}'", s).into()),
+    }
+}
+
+/// Samples matplotlib `LinearSegmentedColormap` data, lists of `[x, y0, y1]` per channel.
+fn parse_segment_data(json: &serde_json::Value) -> Result<Stops, Box<dyn Error + Send + Sync>> {
+    let mut channels = Vec::new();
+    for key in ["red", "green", "blue"] {
+        let segments = json
+            .get(key)
+            .and_then(|segments| segments.as_array())
+            .ok_or("Expected a list of colors or segment data (red, green, blue)")?;
+        let segments: Option<Vec<[f64; 3]>> = segments
+            .iter()
+            .map(|segment| {
+                let values = segment.as_array()?;
+                Some([values.first()?.as_f64()?, values.get(1)?.as_f64()?, values.get(2)?.as_f64()?])
+            })
+            .collect();
+        match segments {
+            Some(segments) if !segments.is_empty() => channels.push(segments),
+            _ => return Err(format!("Invalid segment data for {}", key).into()),
+        }
+    }
+
+    let sample = |segments: &[[f64; 3]], t: f64| {
+        let next = segments.iter().position(|s| s[0] > t).unwrap_or(segments.len());
+        match next {
+            0 => segments[0][2],
+            n if n == segments.len() => segments[n - 1][1],
+            n => {
+                let ([x0, _, y0], [x1, y1, _]) = (segments[n - 1], segments[n]);
+                y0 + (t - x0) / (x1 - x0) * (y1 - y0)
+            }
+        }
+    };
+    Ok((0..SEGMENT_SAMPLES)
+        .map(|i| {
+            let t = i as f64 / (SEGMENT_SAMPLES - 1) as f64;
+            let rgb = [0, 1, 2].map(|c| sample(&channels[c], t));
+            rgb.map(|v| (v * 255.).round().clamp(0., 255.) as u8)
+        })
+        .collect())
+}
+
+/// Converts colors with values from 0 to 1, or 0 to 255 if any value exceeds 1.
+fn to_rgb(rows: &[[f64; 3]]) -> Stops {
+    let scale = if rows.iter().flatten().any(|&v| v > 1.) { 1. } else { 255. };
+    rows.iter()
+        .map(|row| row.map(|v| (v * scale).round().clamp(0., 255.) as u8))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_with_header() {
+        let stops = parse_table("# comment\nred,green,blue\n0,0,0\n1,0.5,1\n").unwrap();
+        assert_eq!(stops, vec![[0, 0, 0], [255, 128, 255]]);
+    }
+
+    #[test]
+    fn table_header_only_in_first_line() {
+        assert!(parse_table("0 0 0\nred green blue\n1 1 1\n").is_err());
+        assert!(parse_table("r g b\nr g b\n0 0 0\n1 1 1\n").is_err());
+    }
+
+    #[test]
+    fn table_scaling() {
+        assert_eq!(parse_table("0 0 0\n1 1 1\n").unwrap(), vec![[0, 0, 0], [255, 255, 255]]);
+        assert_eq!(parse_table("0 0 0\n1 1 255\n").unwrap(), vec![[0, 0, 0], [1, 1, 255]]);
+    }
+
+    #[test]
+    fn table_alpha_column() {
+        let stops = parse_table("r\tg\tb\ta\n0\t0\t0\t1\n255\t128\t0\t0\n").unwrap();
+        assert_eq!(stops, vec![[0, 0, 0], [255, 128, 0]]);
+    }
+
+    #[test]
+    fn json_colors() {
+        let (name, stops) = parse_json(r##"{"name": "lab", "colors": ["#000000", "#ff8000"]}"##).unwrap();
+        assert_eq!(name.as_deref(), Some("lab"));
+        assert_eq!(stops, vec![[0, 0, 0], [255, 128, 0]]);
+
+        let (name, stops) = parse_json("[[0, 0, 0], [1, 0.5, 0, 1]]").unwrap();
+        assert_eq!(name, None);
+        assert_eq!(stops, vec![[0, 0, 0], [255, 128, 0]]);
+    }
+
+    #[test]
+    fn json_mixed_hex_and_rgb() {
+        assert!(parse_json(r##"["#000000", [1, 1, 1]]"##).is_err());
+        assert!(parse_json(r##"[[0, 0, 0], "#ffffff"]"##).is_err());
+    }
+
+    #[test]
+    fn segment_data_endpoints() {
+        let (_, stops) = parse_json(
+            r#"{
+                "red": [[0, 0, 0.2], [1, 0.8, 1]],
+                "green": [[0, 0, 0], [0.5, 1, 0], [1, 1, 1]],
+                "blue": [[0, 1, 1], [1, 0, 0]]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(stops.len(), SEGMENT_SAMPLES);
+        // y1 of the first segment and y0 of the last one
+        assert_eq!(stops[0], [51, 0, 255]);
+        assert_eq!(stops[SEGMENT_SAMPLES - 1], [204, 255, 0]);
+    }
+}
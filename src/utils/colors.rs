@@ -1,9 +1,107 @@
-#[derive(Debug, Clone, Copy)]
+/// RGB color of NaN and infinite voxels unless set with `--nan-color`.
+pub const DEFAULT_NAN_COLOR: [u8; 3] = [255, 0, 255];
+
+/// Color depth used for terminal output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum ColorMode {
+    /// 24 bit RGB colors.
+    #[value(name = "truecolor")]
     TrueColor,
+    /// 256 color palette.
+    #[value(name = "ansi256")]
     Ansi256,
+    /// 16 basic colors, with ordered dithering.
+    #[value(name = "ansi16")]
+    Ansi16,
+    /// No colors, images are shaded with ASCII characters.
+    #[value(name = "ascii")]
+    Ascii,
+}
+
+impl ColorMode {
+    /// Guesses the color depth supported by the terminal from its environment variables.
+    ///
+    /// Inside tmux and screen, true color is only used for `*-direct` terminal types
+    /// or if `COLORTERM` announces it, as it is otherwise commonly not passed through
+    /// to the outer terminal.
+    /// Colors are disabled for `TERM=dumb` and if `NO_COLOR` is set.
+    pub fn detect() -> Self {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let colorterm = var("COLORTERM");
+        let multiplexer =
+            !var("TMUX").is_empty() || term.starts_with("screen") || term.starts_with("tmux");
+
+        if term == "dumb" || !var("NO_COLOR").is_empty() {
+            ColorMode::Ascii
+        } else if term.ends_with("-direct") || colorterm == "truecolor" || colorterm == "24bit" {
+            ColorMode::TrueColor
+        } else if multiplexer {
+            if !var("TMUX").is_empty() || term.contains("256color") {
+                ColorMode::Ansi256
+            } else {
+                ColorMode::Ansi16
+            }
+        } else if !var("KITTY_WINDOW_ID").is_empty()
+            || !var("WT_SESSION").is_empty()
+            || ["iTerm.app", "WezTerm", "vscode", "ghostty"].contains(&var("TERM_PROGRAM").as_str())
+        {
+            ColorMode::TrueColor
+        } else if term.is_empty()
+            || ["linux", "ansi", "cygwin", "rxvt"].contains(&term.as_str())
+            || term.starts_with("vt")
+        {
+            ColorMode::Ansi16
+        } else {
+            ColorMode::Ansi256
+        }
+    }
+}
+
+impl std::fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// 4x4 Bayer matrix for ordered dithering.
+static BAYER4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Ordered dithering threshold in the range (-0.5, 0.5) at the given position.
+fn dither_offset(x: usize, y: usize) -> f64 {
+    (BAYER4[y % 4][x % 4] as f64 + 0.5) / 16. - 0.5
+}
+
+/// Characters of increasing density for shading without colors.
+static SHADES: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// Shading character for the brightness `luma` (0 - 255) at the given cell position,
+/// ordered dithered between adjacent densities.
+pub fn shade(luma: f64, x: usize, y: usize) -> char {
+    let level = luma / 255. * (SHADES.len() - 1) as f64 + dither_offset(x, y);
+    SHADES[(level.round().max(0.) as usize).min(SHADES.len() - 1)]
 }
 
+/// The 16 basic terminal colors with their typical (xterm) RGB values.
+static ANSI16: [(tui::style::Color, [u8; 3]); 16] = [
+    (tui::style::Color::Black, [0, 0, 0]),
+    (tui::style::Color::Red, [205, 0, 0]),
+    (tui::style::Color::Green, [0, 205, 0]),
+    (tui::style::Color::Yellow, [205, 205, 0]),
+    (tui::style::Color::Blue, [0, 0, 238]),
+    (tui::style::Color::Magenta, [205, 0, 205]),
+    (tui::style::Color::Cyan, [0, 205, 205]),
+    (tui::style::Color::Gray, [229, 229, 229]),
+    (tui::style::Color::DarkGray, [127, 127, 127]),
+    (tui::style::Color::LightRed, [255, 0, 0]),
+    (tui::style::Color::LightGreen, [0, 255, 0]),
+    (tui::style::Color::LightYellow, [255, 255, 0]),
+    (tui::style::Color::LightBlue, [92, 92, 255]),
+    (tui::style::Color::LightMagenta, [255, 0, 255]),
+    (tui::style::Color::LightCyan, [0, 255, 255]),
+    (tui::style::Color::White, [255, 255, 255]),
+];
+
 struct HjColor(colorous::Color);
 
 impl ansi_colours::AsRGB for HjColor {
@@ -12,26 +110,41 @@ impl ansi_colours::AsRGB for HjColor {
     }
 }
 
-impl Into<tui::style::Color> for HjColor {
-    fn into(self) -> tui::style::Color {
-        tui::style::Color::Rgb(self.0.r, self.0.g, self.0.b)
+impl From<HjColor> for tui::style::Color {
+    fn from(val: HjColor) -> Self {
+        tui::style::Color::Rgb(val.0.r, val.0.g, val.0.b)
     }
 }
 
 impl HjColor {
     pub fn invert(&self) -> Self {
-        Self {
-            0: colorous::Color {
-                r: 255 - self.0.r,
-                g: 255 - self.0.g,
-                b: 255 - self.0.b,
-            },
-        }
+        Self(colorous::Color {
+            r: 255 - self.0.r,
+            g: 255 - self.0.g,
+            b: 255 - self.0.b,
+        })
     }
 
     pub fn luma(&self) -> f64 {
         0.2126 * self.0.r as f64 + 0.7152 * self.0.g as f64 + 0.0722 * self.0.b as f64
     }
+
+    /// Closest terminal color in the given mode.
+    pub fn termcolor(self, mode: ColorMode) -> tui::style::Color {
+        match mode {
+            ColorMode::TrueColor => self.into(),
+            ColorMode::Ansi256 => tui::style::Color::Indexed(ansi_colours::ansi256_from_rgb(self)),
+            ColorMode::Ansi16 => {
+                let rgb = [self.0.r as i32, self.0.g as i32, self.0.b as i32];
+                ANSI16
+                    .iter()
+                    .min_by_key(|(_, c)| (0..3).map(|i| (rgb[i] - c[i] as i32).pow(2)).sum::<i32>())
+                    .map(|(color, _)| *color)
+                    .unwrap_or(tui::style::Color::Reset)
+            }
+            ColorMode::Ascii => tui::style::Color::Reset,
+        }
+    }
 }
 
 pub fn calc_termcolor_rational(
@@ -42,10 +155,7 @@ pub fn calc_termcolor_rational(
 ) -> tui::style::Color {
     let rgb = HjColor(map.eval_rational(val, val_max));
 
-    match mode {
-        ColorMode::TrueColor => rgb.into(),
-        ColorMode::Ansi256 => tui::style::Color::Indexed(ansi_colours::ansi256_from_rgb(rgb)),
-    }
+    rgb.termcolor(mode)
 }
 
 pub fn calc_termcolor_continuous(
@@ -55,10 +165,7 @@ pub fn calc_termcolor_continuous(
 ) -> tui::style::Color {
     let rgb = HjColor(map.eval_continuous(val));
 
-    match mode {
-        ColorMode::TrueColor => rgb.into(),
-        ColorMode::Ansi256 => tui::style::Color::Indexed(ansi_colours::ansi256_from_rgb(rgb)),
-    }
+    rgb.termcolor(mode)
 }
 
 pub fn calc_termcolor_inverted_rational(
@@ -69,10 +176,7 @@ pub fn calc_termcolor_inverted_rational(
 ) -> tui::style::Color {
     let rgb = HjColor(map.eval_rational(val, val_max)).invert();
 
-    match mode {
-        ColorMode::TrueColor => rgb.into(),
-        ColorMode::Ansi256 => tui::style::Color::Indexed(ansi_colours::ansi256_from_rgb(rgb)),
-    }
+    rgb.termcolor(mode)
 }
 
 pub fn calc_termcolor_inverted_continuous(
@@ -82,16 +186,55 @@ pub fn calc_termcolor_inverted_continuous(
 ) -> tui::style::Color {
     let rgb = HjColor(map.eval_continuous(val)).invert();
 
-    match mode {
-        ColorMode::TrueColor => rgb.into(),
-        ColorMode::Ansi256 => tui::style::Color::Indexed(ansi_colours::ansi256_from_rgb(rgb)),
-    }
+    rgb.termcolor(mode)
 }
 
 pub fn colorous2tui(value: colorous::Color) -> tui::style::Color {
     tui::style::Color::Rgb(value.r, value.g, value.b)
 }
 
+/// RGB components of a terminal color, as used for pixel images.
+pub fn tui2rgb(value: tui::style::Color) -> [u8; 3] {
+    match value {
+        tui::style::Color::Rgb(r, g, b) => [r, g, b],
+        tui::style::Color::Indexed(i) => {
+            let (r, g, b) = ansi_colours::rgb_from_ansi256(i);
+            [r, g, b]
+        }
+        color => ANSI16
+            .iter()
+            .find(|(c, _)| *c == color)
+            .map(|(_, rgb)| *rgb)
+            .unwrap_or([0, 0, 0]),
+    }
+}
+
+/// Terminal color of RGB components.
+pub fn rgb2tui(value: [u8; 3], mode: ColorMode) -> tui::style::Color {
+    let rgb = HjColor(colorous::Color {
+        r: value[0],
+        g: value[1],
+        b: value[2],
+    });
+
+    rgb.termcolor(mode)
+}
+
+/// Black or white, whichever stands out more against the RGB color `value`.
+pub fn max_contrast(value: [u8; 3]) -> tui::style::Color {
+    let luma = HjColor(colorous::Color {
+        r: value[0],
+        g: value[1],
+        b: value[2],
+    })
+    .luma();
+    if luma < 128.0 {
+        tui::style::Color::White
+    } else {
+        tui::style::Color::Black
+    }
+}
+
 pub fn invert_color(value: colorous::Color) -> colorous::Color {
     colorous::Color {
         r: 255 - value.r,
@@ -100,9 +243,38 @@ pub fn invert_color(value: colorous::Color) -> colorous::Color {
     }
 }
 
+/// Colors of a [`ColorMapper`].
+#[derive(Debug, Clone, Copy)]
+pub enum Gradient {
+    Colorous(colorous::Gradient),
+    /// Evenly spaced RGB colors, linearly interpolated.
+    Stops(&'static [[u8; 3]]),
+}
+
+impl Gradient {
+    /// Color at `t`, clamped to the range [0.0, 1.0].
+    pub fn eval_continuous(&self, t: f64) -> colorous::Color {
+        match self {
+            Gradient::Colorous(gradient) => gradient.eval_continuous(t),
+            Gradient::Stops(stops) => {
+                let position = t.clamp(0., 1.) * (stops.len() - 1) as f64;
+                let index = (position.floor() as usize).min(stops.len().saturating_sub(2));
+                let fraction = position - index as f64;
+                let next = stops[(index + 1).min(stops.len() - 1)];
+                let mix = |a: u8, b: u8| (a as f64 + fraction * (b as f64 - a as f64)).round() as u8;
+                colorous::Color {
+                    r: mix(stops[index][0], next[0]),
+                    g: mix(stops[index][1], next[1]),
+                    b: mix(stops[index][2], next[2]),
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ColorMapper {
-    pub gradient: colorous::Gradient,
+    pub gradient: Gradient,
     pub invert: bool,
 }
 
@@ -117,20 +289,45 @@ impl ColorMapper {
                     .eval_continuous(if self.invert { 1.0 - value } else { value }),
             );
 
+        rgb.termcolor(mode)
+    }
+
+    /// Get color, ordered dithered at the pixel position (x, y) in modes with few colors.
+    ///
+    /// `value` must be in the range [0.0, 1.0].
+    pub fn color_dithered(&self, value: f64, mode: ColorMode, x: usize, y: usize) -> tui::style::Color {
         match mode {
-            ColorMode::TrueColor => rgb.into(),
-            ColorMode::Ansi256 => tui::style::Color::Indexed(ansi_colours::ansi256_from_rgb(rgb)),
+            ColorMode::Ansi16 => {
+                let rgb = self
+                    .gradient
+                    .eval_continuous(if self.invert { 1.0 - value } else { value });
+                // roughly half the distance between the levels of the basic colors
+                let offset = dither_offset(x, y) * 96.;
+                let dither = |c: u8| (c as f64 + offset).round().clamp(0., 255.) as u8;
+                HjColor(colorous::Color {
+                    r: dither(rgb.r),
+                    g: dither(rgb.g),
+                    b: dither(rgb.b),
+                })
+                .termcolor(mode)
+            }
+            _ => self.color(value, mode),
         }
     }
 
-    pub fn color_max_contrast(&self, value: f64, _mode: ColorMode) -> tui::style::Color {
-        if HjColor(
+    /// Brightness (0 - 255) of the color.
+    ///
+    /// `value` must be in the range [0.0, 1.0].
+    pub fn luma(&self, value: f64) -> f64 {
+        HjColor(
             self.gradient
                 .eval_continuous(if self.invert { 1.0 - value } else { value }),
         )
         .luma()
-            < 128.0
-        {
+    }
+
+    pub fn color_max_contrast(&self, value: f64, _mode: ColorMode) -> tui::style::Color {
+        if self.luma(value) < 128.0 {
             tui::style::Color::White
         } else {
             tui::style::Color::Black
@@ -139,38 +336,91 @@ impl ColorMapper {
 }
 
 static GREYS: ColorMapper = ColorMapper {
-    gradient: colorous::GREYS,
+    gradient: Gradient::Colorous(colorous::GREYS),
     invert: true,
 };
 static INFERNO: ColorMapper = ColorMapper {
-    gradient: colorous::INFERNO,
+    gradient: Gradient::Colorous(colorous::INFERNO),
     invert: false,
 };
 static TURBO: ColorMapper = ColorMapper {
-    gradient: colorous::TURBO,
+    gradient: Gradient::Colorous(colorous::TURBO),
     invert: false,
 };
 static MAGMA: ColorMapper = ColorMapper {
-    gradient: colorous::MAGMA,
+    gradient: Gradient::Colorous(colorous::MAGMA),
     invert: false,
 };
 /*static PLASMA: ColorMapper = ColorMapper {
-    gradient: colorous::PLASMA,
+    gradient: Gradient::Colorous(colorous::PLASMA),
     invert: false,
 };*/
 static VIRIDIS: ColorMapper = ColorMapper {
-    gradient: colorous::VIRIDIS,
+    gradient: Gradient::Colorous(colorous::VIRIDIS),
     invert: false,
 };
 static CUBEHELIX: ColorMapper = ColorMapper {
-    gradient: colorous::CUBEHELIX,
+    gradient: Gradient::Colorous(colorous::CUBEHELIX),
     invert: false,
 };
 static RAINBOW: ColorMapper = ColorMapper {
-    gradient: colorous::SINEBOW,
+    gradient: Gradient::Colorous(colorous::SINEBOW),
+    invert: false,
+};
+static RDBU: ColorMapper = ColorMapper {
+    gradient: Gradient::Colorous(colorous::RED_BLUE),
+    invert: false,
+};
+static RDBU_R: ColorMapper = ColorMapper {
+    gradient: Gradient::Colorous(colorous::RED_BLUE),
+    invert: true,
+};
+static COOLWARM: ColorMapper = ColorMapper {
+    gradient: Gradient::Stops(&COOLWARM_STOPS),
+    invert: false,
+};
+static PIYG: ColorMapper = ColorMapper {
+    gradient: Gradient::Colorous(colorous::PINK_GREEN),
     invert: false,
 };
 
+/// Moreland's diverging cool to warm color map, in 33 steps.
+static COOLWARM_STOPS: [[u8; 3]; 33] = [
+    [59, 76, 192],
+    [68, 90, 204],
+    [77, 104, 215],
+    [87, 117, 225],
+    [98, 130, 234],
+    [108, 142, 241],
+    [119, 154, 247],
+    [130, 165, 251],
+    [141, 176, 254],
+    [152, 185, 255],
+    [163, 194, 255],
+    [174, 201, 253],
+    [184, 208, 249],
+    [194, 213, 244],
+    [204, 217, 238],
+    [213, 219, 230],
+    [221, 221, 221],
+    [229, 216, 209],
+    [236, 211, 197],
+    [241, 204, 185],
+    [245, 196, 173],
+    [247, 187, 160],
+    [247, 177, 148],
+    [247, 166, 135],
+    [244, 154, 123],
+    [241, 141, 111],
+    [236, 127, 99],
+    [229, 112, 88],
+    [222, 96, 77],
+    [213, 80, 66],
+    [203, 62, 56],
+    [192, 40, 47],
+    [180, 4, 38],
+];
+
 #[derive(Debug, Clone, Copy)]
 pub enum ColorMap {
     Greys,
@@ -181,6 +431,52 @@ pub enum ColorMap {
     Viridis,
     Cubehelix,
     Rainbow,
+    /// Diverging blue (low) to red (high), shown as `RdBu_r` like the reversed
+    /// matplotlib color map.
+    RdBuR,
+    /// Diverging red (low) to blue (high).
+    RdBu,
+    /// Diverging blue (low) to red (high) through light grey.
+    Coolwarm,
+    /// Diverging pink (low) to green (high).
+    PiYG,
+    /// Loaded from a file, index into the maps passed to [`register_color_maps`].
+    Custom(usize),
+}
+
+/// Color maps loaded from files, named and cycled after the built-in ones.
+static CUSTOM_COLOR_MAPS: std::sync::OnceLock<Vec<(String, ColorMapper)>> = std::sync::OnceLock::new();
+
+/// Makes color maps (name and evenly spaced RGB stops) available as [`ColorMap::Custom`].
+///
+/// Can only be called once, before the first use of the color maps. Names have to be
+/// unique, ignoring case, so that every color map can be found by [`ColorMap::from_name`].
+pub fn register_color_maps(maps: Vec<(String, Vec<[u8; 3]>)>) -> Result<(), String> {
+    for (i, (name, _)) in maps.iter().enumerate() {
+        if let Some(color_map) = ColorMap::from_name(name) {
+            return Err(format!("Color map '{}' has the same name as the built-in {}", name, color_map));
+        }
+        if maps[..i].iter().any(|(other, _)| other.eq_ignore_ascii_case(name)) {
+            return Err(format!("Color map '{}' is loaded more than once", name));
+        }
+    }
+    let maps = maps
+        .into_iter()
+        .map(|(name, stops)| {
+            let mapper = ColorMapper {
+                gradient: Gradient::Stops(Box::leak(stops.into_boxed_slice())),
+                invert: false,
+            };
+            (name, mapper)
+        })
+        .collect();
+    CUSTOM_COLOR_MAPS
+        .set(maps)
+        .map_err(|_| "Color maps are already registered".to_owned())
+}
+
+fn custom_color_maps() -> &'static [(String, ColorMapper)] {
+    CUSTOM_COLOR_MAPS.get().map_or(&[], Vec::as_slice)
 }
 
 impl ColorMap {
@@ -194,6 +490,11 @@ impl ColorMap {
             ColorMap::Viridis => &VIRIDIS,
             ColorMap::Cubehelix => &CUBEHELIX,
             ColorMap::Rainbow => &RAINBOW,
+            ColorMap::RdBuR => &RDBU_R,
+            ColorMap::RdBu => &RDBU,
+            ColorMap::Coolwarm => &COOLWARM,
+            ColorMap::PiYG => &PIYG,
+            ColorMap::Custom(index) => &custom_color_maps()[*index].1,
         }
     }
 
@@ -206,13 +507,45 @@ impl ColorMap {
             ColorMap::Plasma =>*/ ColorMap::Viridis,
             ColorMap::Viridis => ColorMap::Cubehelix,
             ColorMap::Cubehelix => ColorMap::Rainbow,
-            ColorMap::Rainbow => ColorMap::Greys,
+            ColorMap::Rainbow => ColorMap::RdBuR,
+            ColorMap::RdBuR => ColorMap::RdBu,
+            ColorMap::RdBu => ColorMap::Coolwarm,
+            ColorMap::Coolwarm => ColorMap::PiYG,
+            ColorMap::PiYG => ColorMap::custom_or_first(0),
+            ColorMap::Custom(index) => ColorMap::custom_or_first(index + 1),
+        }
+    }
+
+    /// The registered color map at `index`, or the first built-in one past the end.
+    fn custom_or_first(index: usize) -> Self {
+        if index < custom_color_maps().len() {
+            ColorMap::Custom(index)
+        } else {
+            ColorMap::Greys
+        }
+    }
+
+    /// Finds a built-in or registered color map by name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut color_map = ColorMap::Greys;
+        loop {
+            if color_map.to_string().eq_ignore_ascii_case(name) {
+                return Some(color_map);
+            }
+            color_map = color_map.next();
+            if let ColorMap::Greys = color_map {
+                return None;
+            }
         }
     }
 }
 
 impl std::fmt::Display for ColorMap {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        std::fmt::Debug::fmt(self, f)
+        match self {
+            ColorMap::Custom(index) => write!(f, "{}", custom_color_maps()[*index].0),
+            ColorMap::RdBuR => write!(f, "RdBu_r"),
+            _ => std::fmt::Debug::fmt(self, f),
+        }
     }
 }
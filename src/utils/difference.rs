@@ -0,0 +1,219 @@
+use ndarray::Zip;
+
+use crate::utils::brain_volume::BrainVolume;
+use crate::widgets::key_value_list_widget::KeyValueList;
+
+/// Voxelwise comparison of two images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffOp {
+    /// a - b
+    Difference,
+    /// a / b
+    Ratio,
+    /// |a - b|
+    Absolute,
+}
+
+impl DiffOp {
+    pub fn apply(&self, a: f64, b: f64) -> f64 {
+        match self {
+            DiffOp::Difference => a - b,
+            DiffOp::Ratio => {
+                if b != 0. {
+                    a / b
+                } else if a == 0. {
+                    1.
+                } else {
+                    f64::NAN
+                }
+            }
+            DiffOp::Absolute => (a - b).abs(),
+        }
+    }
+
+    /// Result of the operation for identical values.
+    pub fn identity(&self) -> f64 {
+        match self {
+            DiffOp::Difference => 0.,
+            DiffOp::Ratio => 1.,
+            DiffOp::Absolute => 0.,
+        }
+    }
+
+    /// Display range of the result, centred on the identity for signed operations.
+    pub fn intensity_range(&self, max_deviation: f64) -> (f64, f64) {
+        let max_deviation = if max_deviation > 0. { max_deviation } else { 1. };
+        match self {
+            DiffOp::Difference | DiffOp::Ratio => (
+                self.identity() - max_deviation,
+                self.identity() + max_deviation,
+            ),
+            DiffOp::Absolute => (0., max_deviation),
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            DiffOp::Difference => "-",
+            DiffOp::Ratio => "/",
+            DiffOp::Absolute => "|-|",
+        }
+    }
+}
+
+impl std::fmt::Display for DiffOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Result of comparing two images.
+#[derive(Debug)]
+pub struct Difference {
+    pub volume: BrainVolume,
+    /// Largest absolute deviation from [`DiffOp::identity`] of all finite voxels.
+    pub max_deviation: f64,
+    pub summary: KeyValueList,
+}
+
+/// Compares the first 3D volume of `volume` voxelwise with `other`,
+/// which is resampled onto the grid of `volume` beforehand.
+///
+/// Voxels outside of `other` are compared against zero.
+pub fn difference(volume: &BrainVolume, other: &BrainVolume, op: DiffOp) -> Difference {
+    let arr = volume.array_view_3d();
+    let other_arr = other.resample_like(volume, 0.);
+    let diff_arr = Zip::from(&arr)
+        .and(&other_arr)
+        .map_collect(|&a, &b| op.apply(a, b));
+
+    let n_voxels = diff_arr.len();
+    let n_identical = Zip::from(&arr)
+        .and(&other_arr)
+        .fold(0usize, |acc, &a, &b| acc + (a == b) as usize);
+
+    let finite: Vec<f64> = diff_arr.iter().copied().filter(|v| v.is_finite()).collect();
+    let n_finite = finite.len().max(1) as f64;
+    let deviations = finite.iter().map(|v| v - op.identity());
+    let min = finite.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = finite.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let mean = finite.iter().sum::<f64>() / n_finite;
+    let max_deviation = deviations.clone().fold(0., |acc: f64, d| acc.max(d.abs()));
+    let mean_abs_deviation = deviations.clone().map(f64::abs).sum::<f64>() / n_finite;
+    let rms_deviation = (deviations.map(|d| d * d).sum::<f64>() / n_finite).sqrt();
+
+    let summary = vec![
+        ("Operation".to_owned(), format!("{}", op)),
+        (
+            "Identical".to_owned(),
+            format!(
+                "{} / {} voxels ({:.2}%)",
+                n_identical,
+                n_voxels,
+                100. * n_identical as f64 / n_voxels as f64
+            ),
+        ),
+        (
+            "Non-finite".to_owned(),
+            format!("{} voxels", n_voxels - finite.len()),
+        ),
+        ("Range".to_owned(), format!("[{}, {}]", min, max)),
+        ("Mean".to_owned(), format!("{}", mean)),
+        ("Max abs. deviation".to_owned(), format!("{}", max_deviation)),
+        (
+            "Mean abs. deviation".to_owned(),
+            format!("{}", mean_abs_deviation),
+        ),
+        ("RMS deviation".to_owned(), format!("{}", rms_deviation)),
+    ];
+
+    let mut header = volume.header.clone();
+    header.dim = [3, header.dim[1], header.dim[2], header.dim[3], 1, 1, 1, 1];
+    header.datatype = nifti::NiftiType::Float64 as i16;
+    header.bitpix = 64;
+    header.scl_slope = 1.;
+    header.scl_inter = 0.;
+
+    Difference {
+        volume: BrainVolume::from_array(diff_arr.into_dyn(), header),
+        max_deviation,
+        summary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::Array3;
+
+    /// Volume with array axes (k, j, i) and the world x axis given by `srow_x`.
+    fn volume(arr: Array3<f64>, srow_x: [f32; 4]) -> BrainVolume {
+        let header = nifti::NiftiHeader {
+            srow_x,
+            srow_y: [0., 1., 0., 0.],
+            srow_z: [0., 0., 1., 0.],
+            ..Default::default()
+        };
+        BrainVolume::from_array(arr.into_dyn(), header)
+    }
+
+    #[test]
+    fn operations() {
+        assert_eq!(DiffOp::Difference.apply(3., 5.), -2.);
+        assert_eq!(DiffOp::Ratio.apply(3., 6.), 0.5);
+        assert_eq!(DiffOp::Absolute.apply(3., 5.), 2.);
+        for op in [DiffOp::Difference, DiffOp::Ratio, DiffOp::Absolute] {
+            assert_eq!(op.apply(4., 4.), op.identity());
+        }
+    }
+
+    #[test]
+    fn ratio_division_by_zero() {
+        assert_eq!(DiffOp::Ratio.apply(0., 0.), 1.);
+        assert!(DiffOp::Ratio.apply(2., 0.).is_nan());
+        assert!(DiffOp::Ratio.apply(-2., 0.).is_nan());
+    }
+
+    #[test]
+    fn difference_on_same_grid() {
+        let a = volume(Array3::from_elem((2, 2, 3), 5.), [1., 0., 0., 0.]);
+        let b = volume(
+            Array3::from_shape_fn((2, 2, 3), |(_, _, i)| i as f64),
+            [1., 0., 0., 0.],
+        );
+        let diff = difference(&a, &b, DiffOp::Difference);
+        let expected = Array3::from_shape_fn((2, 2, 3), |(_, _, i)| 5. - i as f64);
+        assert_eq!(diff.volume.array_view_3d(), expected);
+        assert_eq!(diff.max_deviation, 5.);
+
+        // the zeros of b are not finite in the ratio
+        let ratio = difference(&a, &b, DiffOp::Ratio);
+        assert_eq!(ratio.max_deviation, 4.);
+        assert!(ratio.summary.contains(&("Non-finite".to_owned(), "4 voxels".to_owned())));
+    }
+
+    #[test]
+    fn resample_like_other_grid() {
+        // x = 2 i - 2, so voxel i of the reference grid is at i / 2 + 1 in `other`
+        let other = volume(
+            Array3::from_shape_fn((2, 2, 3), |(k, j, i)| (100 * k + 10 * j + i) as f64),
+            [2., 0., 0., -2.],
+        );
+        let reference = volume(Array3::zeros((2, 2, 6)), [1., 0., 0., 0.]);
+        let resampled = other.resample_like(&reference, -1.);
+        assert_eq!(resampled.dim(), (2, 2, 6));
+        for (k, j) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            let offset = (100 * k + 10 * j) as f64;
+            let row: Vec<f64> = resampled.slice(ndarray::s![k, j, ..]).to_vec();
+            // up to half a voxel beyond the last voxel of `other`, its value is used
+            let expected = [1., 1.5, 2., 2.].map(|v| v + offset);
+            assert_eq!(row[..4], expected);
+            assert_eq!(row[4..], [-1., -1.]);
+        }
+
+        // voxels outside of `other` are compared against zero
+        let diff = difference(&reference, &other, DiffOp::Difference);
+        assert_eq!(diff.volume.array_view_3d()[[0, 0, 5]], 0.);
+        assert_eq!(diff.volume.array_view_3d()[[1, 1, 0]], -111.);
+    }
+}
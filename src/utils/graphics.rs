@@ -0,0 +1,381 @@
+use base64::Engine;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+
+/// Terminal graphics protocols for displaying pixel images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphicsProtocol {
+    /// DEC Sixel graphics (xterm, foot, mlterm, WezTerm, Windows Terminal, ...).
+    Sixel,
+    /// Kitty graphics protocol (kitty, Ghostty, WezTerm, ...).
+    Kitty,
+    /// iTerm2 inline images (iTerm2, WezTerm, mintty, ...).
+    Iterm2,
+}
+
+impl GraphicsProtocol {
+    /// Guesses the protocol supported by the terminal from its environment variables.
+    ///
+    /// Returns `None` for unknown terminals and inside tmux or screen,
+    /// which do not pass images through to the outer terminal.
+    pub fn detect() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+
+        if !var("TMUX").is_empty() || term.starts_with("screen") || term.starts_with("tmux") {
+            None
+        } else if !var("KITTY_WINDOW_ID").is_empty()
+            || term == "xterm-kitty"
+            || term == "xterm-ghostty"
+            || term_program == "ghostty"
+        {
+            Some(GraphicsProtocol::Kitty)
+        } else if term_program == "iTerm.app"
+            || term_program == "WezTerm"
+            || term_program == "mintty"
+        {
+            Some(GraphicsProtocol::Iterm2)
+        } else if term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || term.starts_with("contour")
+            || !var("WT_SESSION").is_empty()
+        {
+            Some(GraphicsProtocol::Sixel)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for GraphicsProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Size of a terminal cell in pixels, if reported by the terminal.
+pub fn cell_size() -> Option<(u16, u16)> {
+    let size = crossterm::terminal::window_size().ok()?;
+    if size.columns == 0 || size.rows == 0 || size.width < size.columns || size.height < size.rows {
+        return None;
+    }
+    Some((size.width / size.columns, size.height / size.rows))
+}
+
+/// RGBA image covering a rectangle of terminal cells.
+#[derive(Debug, Clone)]
+pub struct GraphicsImage {
+    pub area: tui::layout::Rect,
+    pub width: usize,
+    pub height: usize,
+    /// Row-major RGBA pixels, starting at the top left. Pixels with alpha 0
+    /// are transparent and show the terminal background.
+    pub pixels: Vec<u8>,
+}
+
+impl GraphicsImage {
+    /// Hash of the area and pixels, used to skip re-emitting unchanged images.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.area.hash(&mut hasher);
+        self.width.hash(&mut hasher);
+        self.pixels.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Whether some pixels are transparent.
+    pub fn has_transparency(&self) -> bool {
+        self.pixels.chunks_exact(4).any(|p| p[3] == 0)
+    }
+
+    /// Escape sequence displaying the image at the cursor position.
+    ///
+    /// `id` identifies the image slot with the kitty protocol,
+    /// a previous image with the same id is replaced.
+    pub fn encode(&self, protocol: GraphicsProtocol, id: u32) -> String {
+        match protocol {
+            GraphicsProtocol::Sixel => self.encode_sixel(),
+            GraphicsProtocol::Kitty => self.encode_kitty(id),
+            GraphicsProtocol::Iterm2 => self.encode_iterm2(),
+        }
+    }
+
+    fn encode_sixel(&self) -> String {
+        // Quantize to at most 256 colors: pixels are binned with 4 bits per channel,
+        // the most frequent bins (averaged) form the palette.
+        let bin = |p: &[u8]| {
+            ((p[0] as usize >> 4) << 8) | ((p[1] as usize >> 4) << 4) | (p[2] as usize >> 4)
+        };
+        let opaque = |p: &&[u8]| p[3] > 0;
+        let mut bins = vec![(0usize, [0usize; 3]); 4096];
+        for p in self.pixels.chunks_exact(4).filter(opaque) {
+            let b = &mut bins[bin(p)];
+            b.0 += 1;
+            for (sum, &c) in b.1.iter_mut().zip(&p[..3]) {
+                *sum += c as usize;
+            }
+        }
+        let mut used: Vec<usize> = (0..bins.len()).filter(|&b| bins[b].0 > 0).collect();
+        used.sort_by_key(|&b| std::cmp::Reverse(bins[b].0));
+        let average = |b: usize| bins[b].1.map(|c| (c / bins[b].0) as i32);
+        let palette: Vec<[i32; 3]> = used.iter().take(256).map(|&b| average(b)).collect();
+
+        let mut lookup = vec![0u8; bins.len()];
+        for &b in &used {
+            let color = average(b);
+            let nearest = (0..palette.len())
+                .min_by_key(|&i| {
+                    (0..3)
+                        .map(|c| (palette[i][c] - color[c]).pow(2))
+                        .sum::<i32>()
+                })
+                .unwrap_or(0);
+            lookup[b] = nearest as u8;
+        }
+        // transparent pixels are not drawn in any color
+        let indices: Vec<Option<u8>> = self
+            .pixels
+            .chunks_exact(4)
+            .map(|p| opaque(&p).then(|| lookup[bin(p)]))
+            .collect();
+
+        // P2 = 1: pixels without a sixel bit keep their current color.
+        let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", self.width, self.height);
+        for (i, color) in palette.iter().enumerate() {
+            out.push_str(&format!(
+                "#{};2;{};{};{}",
+                i,
+                color[0] * 100 / 255,
+                color[1] * 100 / 255,
+                color[2] * 100 / 255
+            ));
+        }
+
+        let push_run = |out: &mut String, bits: u8, run: usize| {
+            let symbol = (63 + bits) as char;
+            if run > 3 {
+                out.push_str(&format!("!{}{}", run, symbol));
+            } else {
+                (0..run).for_each(|_| out.push(symbol));
+            }
+        };
+
+        for band in (0..self.height).step_by(6) {
+            let rows = std::cmp::min(6, self.height - band);
+            let mut in_band = [false; 256];
+            for row in band..(band + rows) {
+                for index in indices[row * self.width..(row + 1) * self.width].iter().flatten() {
+                    in_band[*index as usize] = true;
+                }
+            }
+            for (color, _) in in_band.iter().enumerate().filter(|(_, &present)| present) {
+                out.push_str(&format!("#{}", color));
+                let mut run = 0;
+                let mut run_bits = 0u8;
+                for x in 0..self.width {
+                    let bits = (0..rows)
+                        .filter(|r| indices[(band + r) * self.width + x] == Some(color as u8))
+                        .fold(0u8, |acc, r| acc | (1 << r));
+                    if bits != run_bits && run > 0 {
+                        push_run(&mut out, run_bits, run);
+                        run = 0;
+                    }
+                    run_bits = bits;
+                    run += 1;
+                }
+                push_run(&mut out, run_bits, run);
+                out.push('$');
+            }
+            out.push('-');
+        }
+        out.push_str("\x1b\\");
+        out
+    }
+
+    fn encode_kitty(&self, id: u32) -> String {
+        // Remove the previous image of this slot, then transmit and display the new one.
+        // q=2 suppresses responses, which would otherwise end up as key events.
+        let mut out = kitty_delete(id);
+        let payload = base64::engine::general_purpose::STANDARD.encode(&self.pixels);
+        let chunks: Vec<&[u8]> = payload.as_bytes().chunks(4096).collect();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let more = (i + 1 < chunks.len()) as u8;
+            if i == 0 {
+                out.push_str(&format!(
+                    "\x1b_Ga=T,f=32,s={},v={},c={},r={},i={},C=1,q=2,m={};",
+                    self.width, self.height, self.area.width, self.area.height, id, more
+                ));
+            } else {
+                out.push_str(&format!("\x1b_Gm={},q=2;", more));
+            }
+            out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+            out.push_str("\x1b\\");
+        }
+        out
+    }
+
+    fn encode_iterm2(&self) -> String {
+        let png = self.to_png();
+        format!(
+            "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0;doNotMoveCursor=1:{}\x07",
+            png.len(),
+            self.area.width,
+            self.area.height,
+            base64::engine::general_purpose::STANDARD.encode(&png)
+        )
+    }
+
+    /// PNG file of the image, with alpha channel.
+    fn to_png(&self) -> Vec<u8> {
+        fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+            png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            let start = png.len();
+            png.extend_from_slice(kind);
+            png.extend_from_slice(data);
+            let mut crc = flate2::Crc::new();
+            crc.update(&png[start..]);
+            png.extend_from_slice(&crc.sum().to_be_bytes());
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bit RGBA, deflate, no filters, not interlaced
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        // every row starts with filter type 0 (none)
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        for row in self.pixels.chunks_exact(self.width * 4) {
+            encoder.write_all(&[0]).and_then(|_| encoder.write_all(row)).unwrap();
+        }
+        let data = encoder.finish().unwrap();
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &data);
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+/// Escape sequence removing the image with the given id displayed with the kitty protocol.
+pub fn kitty_delete(id: u32) -> String {
+    format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", id)
+}
+
+/// Escape sequence removing all images displayed with the kitty protocol.
+pub fn kitty_delete_all() -> &'static str {
+    "\x1b_Ga=d,d=A,q=2\x1b\\"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn image(width: usize, height: usize, pixel: impl Fn(usize, usize) -> [u8; 4]) -> GraphicsImage {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| pixel(x, y))
+            .collect();
+        GraphicsImage {
+            area: tui::layout::Rect::new(0, 0, 10, 5),
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Bitwise CRC-32 as used by PNG.
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &byte in bytes {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+
+    #[test]
+    fn png_chunks() {
+        let img = image(3, 2, |x, y| [x as u8 * 100, y as u8 * 200, 7, 255]);
+        let png = img.to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind_and_data, crc) = rest[4..8 + length + 4].split_at(4 + length);
+            assert_eq!(crc32(kind_and_data), u32::from_be_bytes(crc.try_into().unwrap()));
+            chunks.push((kind_and_data[..4].to_vec(), kind_and_data[4..].to_vec()));
+            rest = &rest[12 + length..];
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| kind.as_slice()).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1, [0, 0, 0, 3, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+
+        // rows of filter type 0 followed by the pixels
+        let mut data = Vec::new();
+        flate2::read::ZlibDecoder::new(chunks[1].1.as_slice())
+            .read_to_end(&mut data)
+            .unwrap();
+        let rows: Vec<u8> = img
+            .pixels
+            .chunks_exact(3 * 4)
+            .flat_map(|row| std::iter::once(0).chain(row.iter().copied()))
+            .collect();
+        assert_eq!(data, rows);
+    }
+
+    #[test]
+    fn sixel_palette_limit() {
+        // 4096 colors, one per bin
+        let img = image(64, 64, |x, y| {
+            [(x % 16 * 16) as u8, (y % 16 * 16) as u8, ((x / 16 + 4 * (y / 16)) * 16) as u8, 255]
+        });
+        let sixel = img.encode(GraphicsProtocol::Sixel, 1);
+        assert!(sixel.starts_with("\x1bP0;1;0q\"1;1;64;64"));
+        assert!(sixel.ends_with("\x1b\\"));
+        assert_eq!(sixel.matches(";2;").count(), 256);
+        assert!(sixel.contains("#255;2;"));
+        assert!(!sixel.contains("#256"));
+    }
+
+    #[test]
+    fn sixel_transparent_pixels() {
+        let img = image(4, 6, |x, _| if x < 2 { [255, 0, 0, 255] } else { [0, 0, 255, 0] });
+        let sixel = img.encode(GraphicsProtocol::Sixel, 1);
+        // a single red color, set in all 6 rows of the first two columns
+        assert_eq!(sixel.matches(";2;").count(), 1);
+        assert!(sixel.contains("#0;2;100;0;0#0~~??$-"));
+    }
+
+    #[test]
+    fn kitty_chunks() {
+        // 3072 bytes are 4096 base64 characters, a single chunk
+        let img = image(32, 24, |_, _| [1, 2, 3, 255]);
+        let kitty = img.encode(GraphicsProtocol::Kitty, 7);
+        let transmissions: Vec<&str> = kitty.split("\x1b\\").filter(|s| !s.is_empty()).collect();
+        assert_eq!(transmissions[0], "\x1b_Ga=d,d=I,i=7,q=2");
+        assert_eq!(transmissions.len(), 2);
+        assert!(transmissions[1].starts_with("\x1b_Ga=T,f=32,s=32,v=24,c=10,r=5,i=7,C=1,q=2,m=0;"));
+
+        // 8000 bytes are 10668 characters
+        let img = image(40, 50, |_, _| [1, 2, 3, 255]);
+        let kitty = img.encode(GraphicsProtocol::Kitty, 7);
+        let transmissions: Vec<&str> = kitty.split("\x1b\\").filter(|s| !s.is_empty()).skip(1).collect();
+        let payloads: Vec<(&str, &str)> = transmissions.iter().map(|t| t.split_once(';').unwrap()).collect();
+        assert_eq!(payloads.len(), 3);
+        assert!(payloads[0].0.starts_with("\x1b_Ga=T,") && payloads[0].0.ends_with(",m=1"));
+        assert_eq!(payloads[1].0, "\x1b_Gm=1,q=2");
+        assert_eq!(payloads[2].0, "\x1b_Gm=0,q=2");
+        let lengths: Vec<usize> = payloads.iter().map(|(_, data)| data.len()).collect();
+        assert_eq!(lengths, [4096, 4096, 2476]);
+        let data: String = payloads.iter().map(|(_, data)| *data).collect();
+        assert_eq!(base64::engine::general_purpose::STANDARD.decode(data).unwrap(), img.pixels);
+    }
+}
@@ -0,0 +1,103 @@
+use std::error::Error;
+
+/// Names of the integer values of a label image (atlas or segmentation).
+#[derive(Debug, Clone, Default)]
+pub struct LabelTable {
+    pub labels: Vec<(i64, String)>,
+}
+
+impl LabelTable {
+    /// Reads a lookup table with one `index name` entry per line.
+    ///
+    /// Tab separated files (e.g. BIDS `dseg.tsv`), FSL label lists and FreeSurfer
+    /// color tables are supported. Header and comment lines are skipped.
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let text = std::fs::read_to_string(path)?;
+        let table = Self::parse(&text);
+        if table.labels.is_empty() {
+            return Err(format!("No labels found in '{}'", path).into());
+        }
+        Ok(table)
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let labels = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let (index, rest) = line.split_once(|c: char| c.is_whitespace())?;
+                let index = index.parse::<i64>().ok()?;
+                let name = if line.contains('\t') {
+                    rest.trim().split('\t').next().unwrap_or_default().to_owned()
+                } else {
+                    // FreeSurfer color tables end with the RGBA values
+                    let words: Vec<&str> = rest.split_whitespace().collect();
+                    let end = words.len() - words.iter().rev().take_while(|w| w.parse::<f64>().is_ok()).count();
+                    words[..end].join(" ")
+                };
+                (!name.is_empty()).then_some((index, name))
+            })
+            .collect();
+        Self { labels }
+    }
+
+    /// Finds a label by name, ignoring case.
+    ///
+    /// Exact matches take precedence, otherwise the name has to be a part of a single label.
+    pub fn find(&self, name: &str) -> Result<(i64, &str), Box<dyn Error + Send + Sync>> {
+        let name = name.to_lowercase();
+        if let Some((index, label)) = self.labels.iter().find(|(_, label)| label.to_lowercase() == name) {
+            return Ok((*index, label));
+        }
+        let matches: Vec<_> = self
+            .labels
+            .iter()
+            .filter(|(_, label)| label.to_lowercase().contains(&name))
+            .collect();
+        match matches.as_slice() {
+            [(index, label)] => Ok((*index, label)),
+            [] => Err(format!("Unknown label '{}'", name).into()),
+            [..] => Err(format!("'{}' matches {} labels", name, matches.len()).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(table: &LabelTable) -> Vec<(i64, &str)> {
+        table.labels.iter().map(|(index, name)| (*index, name.as_str())).collect()
+    }
+
+    #[test]
+    fn freesurfer_color_table() {
+        let table = LabelTable::parse(
+            "#$Id: FreeSurferColorLUT.txt\n\n#No. Label Name:  R   G   B   A\n0   Unknown   0   0   0   0\n17  Left-Hippocampus  220 216 20  0\n",
+        );
+        assert_eq!(names(&table), vec![(0, "Unknown"), (17, "Left-Hippocampus")]);
+    }
+
+    #[test]
+    fn tab_separated_table() {
+        let table = LabelTable::parse("index\tname\tabbreviation\n1\tCerebrospinal fluid\tCSF\n2\tGray matter\tGM\n");
+        assert_eq!(names(&table), vec![(1, "Cerebrospinal fluid"), (2, "Gray matter")]);
+    }
+
+    #[test]
+    fn label_list() {
+        let table = LabelTable::parse("1 Frontal Pole\n2 Insular Cortex\nnot a label\n3\n");
+        assert_eq!(names(&table), vec![(1, "Frontal Pole"), (2, "Insular Cortex")]);
+    }
+
+    #[test]
+    fn find_by_name() {
+        let table = LabelTable::parse("1 Left-Hippocampus\n2 Right-Hippocampus\n3 Hippocampus\n4 Left-Amygdala\n");
+        // exact matches before partial ones, ignoring case
+        assert_eq!(table.find("hippocampus").unwrap(), (3, "Hippocampus"));
+        assert_eq!(table.find("AMYGDALA").unwrap(), (4, "Left-Amygdala"));
+        assert_eq!(table.find("left").unwrap_err().to_string(), "'left' matches 2 labels");
+        assert_eq!(table.find("thalamus").unwrap_err().to_string(), "Unknown label 'thalamus'");
+    }
+}
@@ -1,3 +1,5 @@
+use ndarray::{ArrayD, Axis};
+
 use crate::utils::brain_volume::BrainMetaData;
 
 use crate::widgets::key_value_list_widget::KeyValueList;
@@ -91,17 +93,162 @@ pub fn make_metadata_key_value_list(
     ]
 }
 
+/// Summary statistics of all voxels of `arr`, including all volumes of 4D data.
+///
+/// Non-finite values are counted but excluded from the statistics. Unique values are
+/// only counted for integer data types (`integer`), which are usually label images.
+pub fn make_statistics_key_value_list(arr: &ArrayD<f64>, integer: bool) -> KeyValueList {
+    let n_voxels = arr.len();
+    let mut finite: Vec<f64> = arr.iter().copied().filter(|v| v.is_finite()).collect();
+    let n_nan = arr.iter().filter(|v| v.is_nan()).count();
+    let n_inf = n_voxels - finite.len() - n_nan;
+    let n_nonzero = finite.iter().filter(|&&v| v != 0.).count() + n_inf;
+
+    let n = finite.len() as f64;
+    let mean = finite.iter().sum::<f64>() / n;
+    let std = (finite.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let min = finite.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = finite.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    let mut items = vec![
+        ("Range".to_owned(), format!("[{}, {}]", min, max)),
+        ("Mean".to_owned(), format!("{:.4}", mean)),
+        ("Std".to_owned(), format!("{:.4}", std)),
+    ];
+    if !finite.is_empty() {
+        let [p2, median, p98] = [0.02, 0.5, 0.98].map(|q| percentile(&mut finite, q));
+        items.push(("Median".to_owned(), format!("{}", median)));
+        items.push(("2nd-98th percentile".to_owned(), format!("[{}, {}]", p2, p98)));
+    }
+    items.push((
+        "Non-zero".to_owned(),
+        format!(
+            "{} / {} voxels ({:.2}%)",
+            n_nonzero,
+            n_voxels,
+            100. * n_nonzero as f64 / n_voxels as f64
+        ),
+    ));
+    items.push(("NaN / Inf".to_owned(), format!("{} / {} voxels", n_nan, n_inf)));
+
+    if integer {
+        let unique: std::collections::HashSet<u64> = finite.iter().map(|v| v.to_bits()).collect();
+        items.push(("Unique values".to_owned(), format!("{}", unique.len())));
+    }
+
+    items.push((
+        "Non-zero bounds".to_owned(),
+        match nonzero_bounds(arr) {
+            Some((start, end)) => format!("{:?} to {:?} (voxels)", start, end),
+            None => "<empty>".to_owned(),
+        },
+    ));
+    items
+}
+
+/// Percentile interpolated linearly between the closest ranks, `q` between 0 and 1.
+/// Reorders `values`.
+fn percentile(values: &mut [f64], q: f64) -> f64 {
+    let position = q * (values.len() - 1) as f64;
+    let rank = position.floor() as usize;
+    let (_, &mut lower, above) = values.select_nth_unstable_by(rank, f64::total_cmp);
+    let fraction = position - rank as f64;
+    if fraction == 0. {
+        return lower;
+    }
+    let upper = above.iter().copied().fold(f64::INFINITY, f64::min);
+    lower + fraction * (upper - lower)
+}
+
+/// First and last voxel index (i, j, k) along each spatial axis with a non-zero value in any volume.
+fn nonzero_bounds(arr: &ArrayD<f64>) -> Option<([usize; 3], [usize; 3])> {
+    let mut start = [0; 3];
+    let mut end = [0; 3];
+    for axis in 0..3 {
+        // the array axes are reversed, (..., t, k, j, i)
+        let nonzero: Vec<usize> = arr
+            .axis_iter(Axis(arr.ndim() - 1 - axis))
+            .enumerate()
+            .filter(|(_, plane)| plane.iter().any(|&v| v != 0. && !v.is_nan()))
+            .map(|(i, _)| i)
+            .collect();
+        start[axis] = *nonzero.first()?;
+        end[axis] = *nonzero.last()?;
+    }
+    Some((start, end))
+}
 
 pub fn nifti_type_is_integer(t: nifti::NiftiType) -> bool {
-    match t {
-        nifti::NiftiType::Int8 => true,
-        nifti::NiftiType::Int16 => true,
-        nifti::NiftiType::Int32 => true,
-        nifti::NiftiType::Int64 => true,
-        nifti::NiftiType::Uint8 => true,
-        nifti::NiftiType::Uint16 => true,
-        nifti::NiftiType::Uint32 => true,
-        nifti::NiftiType::Uint64 => true,
-        _ => false,
+    matches!(
+        t,
+        nifti::NiftiType::Int8
+            | nifti::NiftiType::Int16
+            | nifti::NiftiType::Int32
+            | nifti::NiftiType::Int64
+            | nifti::NiftiType::Uint8
+            | nifti::NiftiType::Uint16
+            | nifti::NiftiType::Uint32
+            | nifti::NiftiType::Uint64
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_of_odd_and_even_counts() {
+        assert_eq!(percentile(&mut [5., 1., 3.], 0.5), 3.);
+        assert_eq!(percentile(&mut [4., 1., 3., 2.], 0.5), 2.5);
+        assert_eq!(percentile(&mut [4., 1., 3., 2.], 0.), 1.);
+        assert_eq!(percentile(&mut [4., 1., 3., 2.], 1.), 4.);
+        let mut values: Vec<f64> = (0..=100).rev().map(f64::from).collect();
+        assert_eq!(percentile(&mut values, 0.02), 2.);
+        assert_eq!(percentile(&mut values, 0.98), 98.);
+        assert_eq!(percentile(&mut [7.], 0.5), 7.);
+    }
+
+    #[test]
+    fn nonzero_bounds_of_3d_and_4d_arrays() {
+        // array axes are (k, j, i), the bounds (i, j, k)
+        let mut arr = ArrayD::<f64>::zeros(vec![4, 5, 6]);
+        arr[[1, 2, 3]] = 1.;
+        arr[[2, 4, 1]] = -1.;
+        assert_eq!(nonzero_bounds(&arr), Some(([1, 2, 1], [3, 4, 2])));
+
+        // any volume of 4D data, (t, k, j, i)
+        let mut arr = ArrayD::<f64>::zeros(vec![2, 4, 5, 6]);
+        arr[[0, 1, 2, 3]] = 1.;
+        arr[[1, 3, 0, 5]] = 1.;
+        assert_eq!(nonzero_bounds(&arr), Some(([3, 0, 1], [5, 2, 3])));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn nonzero_bounds_of_empty_and_nan_arrays() {
+        let mut arr = ArrayD::<f64>::zeros(vec![4, 5, 6]);
+        assert_eq!(nonzero_bounds(&arr), None);
+
+        // NaN is not a value, unlike Inf
+        arr[[0, 0, 0]] = f64::NAN;
+        assert_eq!(nonzero_bounds(&arr), None);
+        arr[[3, 4, 5]] = f64::INFINITY;
+        assert_eq!(nonzero_bounds(&arr), Some(([5, 4, 3], [5, 4, 3])));
+    }
+
+    #[test]
+    fn statistics_without_finite_values() {
+        let arr = ArrayD::<f64>::from_elem(vec![2, 2, 2], f64::NAN);
+        let items = make_statistics_key_value_list(&arr, false);
+        let value = |key: &str| items.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        assert_eq!(value("NaN / Inf"), Some("8 / 0 voxels"));
+        assert_eq!(value("Median"), None);
+        assert_eq!(value("Non-zero bounds"), Some("<empty>"));
+
+        let arr = ArrayD::<f64>::zeros(vec![2, 2, 2]);
+        let items = make_statistics_key_value_list(&arr, true);
+        let value = |key: &str| items.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        assert_eq!(value("Median"), Some("0"));
+        assert_eq!(value("Non-zero"), Some("0 / 8 voxels (0.00%)"));
+        assert_eq!(value("Unique values"), Some("1"));
+    }
+}
@@ -3,4 +3,10 @@ pub mod argminmax2;
 pub mod slice_cache;
 pub mod metadata;
 pub mod sampling;
-pub mod brain_volume;
\ No newline at end of file
+pub mod brain_volume;
+pub mod difference;
+pub mod graphics;
+pub mod labels;
+pub mod clusters;
+pub mod roi;
+pub mod color_map_files;
@@ -0,0 +1,183 @@
+use ndarray::Axis;
+
+use crate::utils::brain_volume::BrainVolume;
+use crate::utils::sampling;
+use crate::widgets::key_value_list_widget::KeyValueList;
+
+/// Shape of a region of interest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoiShape {
+    /// Cube of half edge length `radius` around the crosshair, aligned with the world axes.
+    Box,
+    /// Sphere of `radius` around the crosshair.
+    Sphere,
+    /// Nonzero voxels of a mask image.
+    Mask,
+}
+
+impl std::fmt::Display for RoiShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Roi {
+    pub shape: RoiShape,
+    /// Radius in world units, unused by [`RoiShape::Mask`].
+    pub radius: f64,
+}
+
+impl Roi {
+    /// Indices of the voxels of `volume` within the region around the world position `center`.
+    ///
+    /// Voxels belong to a mask if the nearest mask voxel is nonzero.
+    pub fn voxels(
+        &self,
+        volume: &BrainVolume,
+        center: &[f64],
+        mask: Option<&BrainVolume>,
+    ) -> Vec<[usize; 3]> {
+        let r = self.radius;
+        // world region that contains the region of interest
+        let bounds = match (self.shape, mask) {
+            (RoiShape::Mask, Some(mask)) => mask.world_bounds,
+            (RoiShape::Mask, None) => return Vec::new(),
+            _ => sampling::Cube::new(
+                center[0] - r,
+                center[1] - r,
+                center[2] - r,
+                center[0] + r,
+                center[1] + r,
+                center[2] + r,
+            ),
+        };
+        let local_corners = sampling::coords_apply_affine_transform(
+            &bounds.corner_coords().view(),
+            &volume.affine_inv.view(),
+        );
+        let local_bounds = sampling::bounding_cube_from_coords(&local_corners.view());
+        let shape = volume.array_view_3d().dim();
+        let range = |min: f64, max: f64, size: usize| {
+            let start = min.floor().max(0.) as usize;
+            let end = (max.ceil() + 1.).clamp(0., size as f64) as usize;
+            start..end
+        };
+
+        let mut voxels = Vec::new();
+        for i in range(local_bounds.x0, local_bounds.x1, shape.0) {
+            for j in range(local_bounds.y0, local_bounds.y1, shape.1) {
+                for k in range(local_bounds.z0, local_bounds.z1, shape.2) {
+                    let world = volume.voxel_to_world(&[i as f64, j as f64, k as f64]);
+                    let inside = match (self.shape, mask) {
+                        (RoiShape::Box, _) => (0..3).all(|a| (world[a] - center[a]).abs() <= r),
+                        (RoiShape::Sphere, _) => {
+                            (0..3).map(|a| (world[a] - center[a]).powi(2)).sum::<f64>() <= r * r
+                        }
+                        (RoiShape::Mask, Some(mask)) => {
+                            mask.value_at(&world).is_some_and(|value| value != 0.)
+                        }
+                        (RoiShape::Mask, None) => false,
+                    };
+                    if inside {
+                        voxels.push([i, j, k]);
+                    }
+                }
+            }
+        }
+        voxels
+    }
+}
+
+/// Summary statistics of the voxels in a region of interest.
+#[derive(Debug, Clone)]
+pub struct RoiStats {
+    pub count: usize,
+    /// Number of NaN and infinite voxels, which are left out of the statistics.
+    pub non_finite: usize,
+    /// Volume in cubic world units.
+    pub volume: f64,
+    pub mean: f64,
+    pub std: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Mean of the finite voxels of the region in each volume of 4D data.
+    pub time_series: Option<Vec<f64>>,
+}
+
+impl RoiStats {
+    /// Statistics of `voxels` in the first volume, and their mean over time for 4D data.
+    pub fn new(volume: &BrainVolume, voxels: &[[usize; 3]]) -> Self {
+        let arr = volume.array_view_3d();
+        let mut values: Vec<f64> = voxels
+            .iter()
+            .map(|&voxel| arr[voxel])
+            .filter(|v| v.is_finite())
+            .collect();
+        values.sort_by(f64::total_cmp);
+
+        let count = voxels.len();
+        let finite = values.len();
+        let n = finite as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let std = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+        let median = match finite {
+            0 => f64::NAN,
+            _ if finite.is_multiple_of(2) => (values[finite / 2 - 1] + values[finite / 2]) / 2.,
+            _ => values[finite / 2],
+        };
+        let voxel_volume = determinant_3x3(&volume.affine).abs();
+
+        let ndim = volume.arr.ndim();
+        let time_series = (ndim > 3 && volume.arr.shape()[ndim - 4] > 1).then(|| {
+            // the array axes are reversed, further dimensions are fixed to their first index
+            let mut series = volume.arr.view();
+            while series.ndim() > 4 {
+                series = series.index_axis_move(Axis(0), 0);
+            }
+            series
+                .axis_iter(Axis(0))
+                .map(|frame| {
+                    let (sum, n) = voxels
+                        .iter()
+                        .map(|&voxel| frame[voxel])
+                        .filter(|v| v.is_finite())
+                        .fold((0., 0.), |(sum, n), v| (sum + v, n + 1.));
+                    sum / n
+                })
+                .collect()
+        });
+
+        Self {
+            count,
+            non_finite: count - finite,
+            volume: count as f64 * voxel_volume,
+            mean,
+            std,
+            median,
+            min: values.first().copied().unwrap_or(f64::NAN),
+            max: values.last().copied().unwrap_or(f64::NAN),
+            time_series,
+        }
+    }
+
+    pub fn key_value_list(&self, units: &str) -> KeyValueList {
+        vec![
+            ("Voxels".to_owned(), format!("{}", self.count)),
+            ("NaN / Inf".to_owned(), format!("{} voxels skipped", self.non_finite)),
+            ("Volume".to_owned(), format!("{:.1} {}³", self.volume, units)),
+            ("Mean".to_owned(), format!("{:.4}", self.mean)),
+            ("Std".to_owned(), format!("{:.4}", self.std)),
+            ("Median".to_owned(), format!("{:.4}", self.median)),
+            ("Min".to_owned(), format!("{:.4}", self.min)),
+            ("Max".to_owned(), format!("{:.4}", self.max)),
+        ]
+    }
+}
+
+fn determinant_3x3(m: &ndarray::Array2<f64>) -> f64 {
+    m[[0, 0]] * (m[[1, 1]] * m[[2, 2]] - m[[1, 2]] * m[[2, 1]])
+        - m[[0, 1]] * (m[[1, 0]] * m[[2, 2]] - m[[1, 2]] * m[[2, 0]])
+        + m[[0, 2]] * (m[[1, 0]] * m[[2, 1]] - m[[1, 1]] * m[[2, 0]])
+}
@@ -49,7 +49,80 @@ where
         SliceAxis::Y => 2,
         SliceAxis::Z => 1,
     }];
-    return (index, x_index, y_index);
+    (index, x_index, y_index)
+}
+
+/// Coordinate system in which slices are sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SliceSpace {
+    /// Scanner space (z, y, x) given by the affine.
+    World,
+    /// Array axes (k, j, i) of the data.
+    Voxel,
+}
+
+/// What is displayed along the slice axis: a thin slice or a projection through the volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Projection {
+    Slice,
+    /// Maximum intensity projection (MIP).
+    Max,
+    /// Minimum intensity projection (MinIP).
+    Min,
+    /// Mean intensity along the projection direction.
+    Mean,
+}
+
+impl Projection {
+    pub fn next(&self) -> Self {
+        match self {
+            Projection::Slice => Projection::Max,
+            Projection::Max => Projection::Min,
+            Projection::Min => Projection::Mean,
+            Projection::Mean => Projection::Slice,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Projection::Slice => "Slice",
+            Projection::Max => "MIP",
+            Projection::Min => "MinIP",
+            Projection::Mean => "Mean",
+        }
+    }
+}
+
+/// Coordinate system of displayed slices: world or voxel space,
+/// with world space slices optionally rotated around a pivot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SliceFrame {
+    pub space: SliceSpace,
+    /// Rotation in degrees about the x, y and z axis, see [`rotation_about`].
+    pub rotation: [f64; 3],
+    /// World position the slices are rotated around.
+    pub pivot: [f64; 3],
+}
+
+impl SliceFrame {
+    pub fn new(space: SliceSpace) -> Self {
+        Self {
+            space,
+            rotation: [0.; 3],
+            pivot: [0.; 3],
+        }
+    }
+
+    /// Whether slices are sampled along rotated planes.
+    pub fn oblique(&self) -> bool {
+        self.space == SliceSpace::World && self.rotation.iter().any(|&a| a != 0.)
+    }
+
+    /// Transform from frame to world coordinates for oblique slices.
+    pub fn transform(&self) -> Option<Array2<f64>> {
+        self.oblique()
+            .then(|| rotation_about(self.rotation, &self.pivot))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -118,6 +191,23 @@ impl Cube {
             self.zd,
         ]
     }
+
+    /// Cube with the size of this cube divided by `zoom`, centered at `center`
+    /// but shifted to stay within this cube.
+    pub fn zoomed(&self, center: &[f64], zoom: f64) -> Cube {
+        if zoom <= 1. {
+            return *self;
+        }
+        let range = |min: f64, max: f64, size: f64, c: f64| {
+            let half = size / zoom / 2.;
+            let c = c.clamp(min + half, max - half);
+            (c - half, c + half)
+        };
+        let (x0, x1) = range(self.x0, self.x1, self.xd, center[0]);
+        let (y0, y1) = range(self.y0, self.y1, self.yd, center[1]);
+        let (z0, z1) = range(self.z0, self.z1, self.zd, center[2]);
+        Cube::new(x0, y0, z0, x1, y1, z1)
+    }
 }
 
 pub fn bounding_cube_from_shape_3d(shape: &[usize]) -> Cube {
@@ -125,12 +215,13 @@ pub fn bounding_cube_from_shape_3d(shape: &[usize]) -> Cube {
     Cube::new(0., 0., 0., x, y, z)
 }
 
+/// Bounding box of homogeneous coordinates (4 x n).
 pub fn bounding_cube_from_coords(coords: &ndarray::ArrayView2<f64>) -> Cube {
     let min = coords.map_axis(ndarray::Axis(1), |row| {
-        row.iter().take(3).fold(f64::MAX, |acc, &x| acc.min(x))
+        row.iter().fold(f64::MAX, |acc, &x| acc.min(x))
     });
     let max = coords.map_axis(ndarray::Axis(1), |row| {
-        row.iter().take(3).fold(f64::MIN, |acc, &x| acc.max(x))
+        row.iter().fold(f64::MIN, |acc, &x| acc.max(x))
     });
     Cube::new(min[0], min[1], min[2], max[0], max[1], max[2])
 }
@@ -154,12 +245,17 @@ pub fn invert_affine_transform(affine: &ndarray::ArrayView2<f64>) -> ndarray::Ar
     ].reversed_axes()
 }
 
+/// Homogeneous coordinates (4 x width * height) of a slice through `cube` at `out_depth` along `axis`.
+///
+/// An oblique plane is sampled by additionally applying `transform` (4 x 4) to the
+/// coordinates of the axis-aligned plane, e.g. a rotation from [`rotation_about`].
 pub fn slice_cube_3d_coords(
     axis: SliceAxis,
     out_width: usize,
     out_height: usize,
     out_depth: f64,
     cube: Cube,
+    transform: Option<&ndarray::ArrayView2<f64>>,
 ) -> Array2<f64> {
     let (h_min, v_min, h_max, v_max) = match axis {
         SliceAxis::X => (cube.y0, cube.z0, cube.y1, cube.z1),
@@ -197,13 +293,54 @@ pub fn slice_cube_3d_coords(
             idx += 1;
         }
     }
-    unsafe { coords.assume_init().reversed_axes() }
+    let coords = unsafe { coords.assume_init().reversed_axes() };
+    match transform {
+        Some(transform) => coords_apply_affine_transform(&coords.view(), transform),
+        None => coords,
+    }
 }
 
+/// Affine transform (4 x 4) rotating by `angles` (degrees about the x, y and z axis,
+/// applied in this order) around `pivot`.
+///
+/// Like the world coordinates of [`BrainVolume`](crate::utils::brain_volume::BrainVolume),
+/// `pivot` and the transform are in the axis order (z, y, x).
+pub fn rotation_about(angles: [f64; 3], pivot: &[f64]) -> Array2<f64> {
+    let [rx, ry, rz] = angles.map(f64::to_radians);
+    let rotation_x = ndarray::array![
+        [1., 0., 0.],
+        [0., rx.cos(), -rx.sin()],
+        [0., rx.sin(), rx.cos()],
+    ];
+    let rotation_y = ndarray::array![
+        [ry.cos(), 0., ry.sin()],
+        [0., 1., 0.],
+        [-ry.sin(), 0., ry.cos()],
+    ];
+    let rotation_z = ndarray::array![
+        [rz.cos(), -rz.sin(), 0.],
+        [rz.sin(), rz.cos(), 0.],
+        [0., 0., 1.],
+    ];
+    let rotation = rotation_z.dot(&rotation_y).dot(&rotation_x);
+    let rotation = rotation.slice(s![..;-1, ..;-1]);
+
+    // p' = R (p - pivot) + pivot
+    let pivot = ndarray::array![pivot[0], pivot[1], pivot[2]];
+    let translation = &pivot - &rotation.dot(&pivot);
+    let mut transform = Array2::<f64>::eye(4);
+    transform.slice_mut(s![..3, ..3]).assign(&rotation);
+    transform.slice_mut(s![..3, 3]).assign(&translation);
+    transform
+}
+
+/// Samples `input_array` at `coords` with trilinear interpolation.
+///
+/// Coordinates more than half a voxel outside of the array are set to `default_value`.
 pub fn map_coordinates_3d(
     input_array: &ArrayView3<f64>,
     coords: &ArrayView2<f64>,
-    _default_value: f64,
+    default_value: f64,
 ) -> Array1<f64> {
     let coords = coords.reversed_axes();
     let shape = input_array.shape();
@@ -211,13 +348,17 @@ pub fn map_coordinates_3d(
     let mut output_array = Array1::<f64>::zeros(num_coords);
 
     for (idx, coord) in coords.axis_iter(ndarray::Axis(0)).enumerate() {
-        let interpolated_value ;
+        if (0..3).any(|i| coord[i] < -0.5 || coord[i] > shape[i] as f64 - 0.5) {
+            output_array[idx] = default_value;
+            continue;
+        }
 
-        // Clamp coordinates to the array bounds
+        // Clamp coordinates to the array bounds, the last voxel along an axis
+        // has no neighbour to interpolate with but gets the full weight
         let coord = ndarray::array![
-            coord[0].clamp(0., shape[0] as f64 - 2.),
-            coord[1].clamp(0., shape[1] as f64 - 2.),
-            coord[2].clamp(0., shape[2] as f64 - 2.),
+            coord[0].clamp(0., shape[0] as f64 - 1.),
+            coord[1].clamp(0., shape[1] as f64 - 1.),
+            coord[2].clamp(0., shape[2] as f64 - 1.),
             1.
         ];
 
@@ -235,15 +376,23 @@ pub fn map_coordinates_3d(
             coord[2] - z as f64,
         );
 
-        // Trilinear interpolation
-        interpolated_value = (1.0 - dx) * (1.0 - dy) * (1.0 - dz) * input_array[[x, y, z]]
-            + dx * (1.0 - dy) * (1.0 - dz) * input_array[[x + 1, y, z]]
-            + (1.0 - dx) * dy * (1.0 - dz) * input_array[[x, y + 1, z]]
-            + dx * dy * (1.0 - dz) * input_array[[x + 1, y + 1, z]]
-            + (1.0 - dx) * (1.0 - dy) * dz * input_array[[x, y, z + 1]]
-            + dx * (1.0 - dy) * dz * input_array[[x + 1, y, z + 1]]
-            + (1.0 - dx) * dy * dz * input_array[[x, y + 1, z + 1]]
-            + dx * dy * dz * input_array[[x + 1, y + 1, z + 1]];
+        // Trilinear interpolation, corners without weight are skipped so that
+        // NaN and Inf only spread to samples they contribute to
+        let mut interpolated_value = 0.;
+        for (corner, weight) in [
+            ([x, y, z], (1.0 - dx) * (1.0 - dy) * (1.0 - dz)),
+            ([x + 1, y, z], dx * (1.0 - dy) * (1.0 - dz)),
+            ([x, y + 1, z], (1.0 - dx) * dy * (1.0 - dz)),
+            ([x + 1, y + 1, z], dx * dy * (1.0 - dz)),
+            ([x, y, z + 1], (1.0 - dx) * (1.0 - dy) * dz),
+            ([x + 1, y, z + 1], dx * (1.0 - dy) * dz),
+            ([x, y + 1, z + 1], (1.0 - dx) * dy * dz),
+            ([x + 1, y + 1, z + 1], dx * dy * dz),
+        ] {
+            if weight > 0. {
+                interpolated_value += weight * input_array[corner];
+            }
+        }
 
         output_array[idx] = interpolated_value;
     }
@@ -278,4 +427,86 @@ pub fn downsample_2d_array(
     }
     
     result
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn bounding_cube_of_rotated_corners() {
+        let cube = Cube::new(0., 0., 0., 2., 4., 6.);
+        let rotation = rotation_about([45., 0., 0.], &[0., 0., 0.]);
+        let corners = coords_apply_affine_transform(&cube.corner_coords().view(), &rotation.view());
+        let bounds = bounding_cube_from_coords(&corners.view());
+        // about x, the world axes are (z, y, x): z' = (z + y) / sqrt(2) and y' = (y - z) / sqrt(2),
+        // the extremes are at different corners
+        let s = std::f64::consts::SQRT_2;
+        assert_close(bounds.x0, 0.);
+        assert_close(bounds.x1, 6. / s);
+        assert_close(bounds.y0, -2. / s);
+        assert_close(bounds.y1, 4. / s);
+        assert_close(bounds.z0, 0.);
+        assert_close(bounds.z1, 6.);
+    }
+
+    #[test]
+    fn rotation_keeps_pivot_fixed() {
+        let pivot = [3., -2., 10.];
+        let rotation = rotation_about([20., -35., 80.], &pivot);
+        let moved = rotation.dot(&ndarray::array![pivot[0], pivot[1], pivot[2], 1.]);
+        for axis in 0..3 {
+            assert_close(moved[axis], pivot[axis]);
+        }
+        // other points keep their distance to the pivot
+        let point = ndarray::array![5., 1., 4., 1.];
+        let moved = rotation.dot(&point);
+        let distance = |p: &Array1<f64>| (0..3).map(|a| (p[a] - pivot[a]).powi(2)).sum::<f64>();
+        assert_close(distance(&moved), distance(&point));
+    }
+
+    #[test]
+    fn rotation_about_x_in_world_axis_order() {
+        // world axes are (z, y, x), y turns towards z
+        let rotation = rotation_about([90., 0., 0.], &[0., 0., 0.]);
+        let moved = rotation.dot(&ndarray::array![0., 1., 0., 1.]);
+        assert_close(moved[0], 1.);
+        assert_close(moved[1], 0.);
+        assert_close(moved[2], 0.);
+    }
+
+    #[test]
+    fn identity_rotation_reproduces_axis_aligned_slices() {
+        let cube = Cube::new(-10., 0., 5., 10., 30., 25.);
+        let identity = rotation_about([0.; 3], &[4., 5., 6.]);
+        for axis in [SliceAxis::X, SliceAxis::Y, SliceAxis::Z] {
+            let aligned = slice_cube_3d_coords(axis, 7, 5, 12., cube, None);
+            let rotated = slice_cube_3d_coords(axis, 7, 5, 12., cube, Some(&identity.view()));
+            assert_eq!(aligned.dim(), (4, 35));
+            for (a, b) in aligned.iter().zip(rotated.iter()) {
+                assert_close(*a, *b);
+            }
+        }
+    }
+
+    #[test]
+    fn zoomed_bounds_stay_inside() {
+        let cube = Cube::new(0., 0., 0., 100., 50., 20.);
+        let zoomed = cube.zoomed(&[50., 25., 10.], 2.);
+        assert_eq!(zoomed.min().to_vec(), vec![25., 12.5, 5., 1.]);
+        assert_eq!(zoomed.size().to_vec(), vec![50., 25., 10.]);
+
+        // shifted back in near the corners
+        let zoomed = cube.zoomed(&[-20., 49., 1.], 4.);
+        assert_eq!(zoomed.min().to_vec(), vec![0., 37.5, 0., 1.]);
+        assert_eq!(zoomed.max().to_vec(), vec![25., 50., 5., 1.]);
+
+        // zooming out shows the whole cube
+        let zoomed = cube.zoomed(&[90., 0., 0.], 0.5);
+        assert_eq!(zoomed.min(), cube.min());
+        assert_eq!(zoomed.max(), cube.max());
+    }
+}
@@ -7,26 +7,84 @@ use super::brain_volume;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CachableSlicerParams {
+    pub space: sampling::SliceSpace,
     pub axis: sampling::SliceAxis,
     pub depth: N64,
     pub default_value: N64,
     pub resolution: (usize, usize),
+    /// Region in `space` that is sampled (x0, y0, z0, x1, y1, z1),
+    /// i.e. the field of view after zooming and panning.
+    pub bounds: [N64; 6],
+    /// Rotation of the plane in degrees about the x, y and z axis, see [`sampling::rotation_about`].
+    pub rotation: [N64; 3],
+    /// Point the plane is rotated around, zero for axis-aligned planes.
+    pub pivot: [N64; 3],
+    pub projection: sampling::Projection,
 }
 
 impl CachableSlicerParams {
     pub fn new(
+        space: sampling::SliceSpace,
         axis: sampling::SliceAxis,
         depth: N64,
         default_value: N64,
         resolution: (usize, usize),
+        bounds: sampling::Cube,
     ) -> Self {
         Self {
+            space,
             axis,
             depth,
             default_value,
             resolution,
+            bounds: [
+                n64(bounds.x0),
+                n64(bounds.y0),
+                n64(bounds.z0),
+                n64(bounds.x1),
+                n64(bounds.y1),
+                n64(bounds.z1),
+            ],
+            rotation: [n64(0.); 3],
+            pivot: [n64(0.); 3],
+            projection: sampling::Projection::Slice,
         }
     }
+
+    /// Samples an oblique plane, rotated by `angles` (degrees) around `pivot`.
+    ///
+    /// Only applies to world space slices.
+    pub fn rotated(mut self, angles: [f64; 3], pivot: &[f64]) -> Self {
+        if self.space == sampling::SliceSpace::World && angles.iter().any(|&a| a != 0.) {
+            self.rotation = angles.map(n64);
+            self.pivot = [n64(pivot[0]), n64(pivot[1]), n64(pivot[2])];
+        }
+        self
+    }
+
+    /// Projects through the whole volume instead of sampling a slice at `depth`.
+    pub fn projected(mut self, projection: sampling::Projection) -> Self {
+        if projection != sampling::Projection::Slice {
+            // projections are the same at every depth
+            self.depth = n64(0.);
+            self.projection = projection;
+        }
+        self
+    }
+
+    /// Affine transform of the axis-aligned plane, if oblique.
+    pub fn transform(&self) -> Option<ndarray::Array2<f64>> {
+        let angles = self.rotation.map(f64::from);
+        if angles.iter().all(|&a| a == 0.) {
+            return None;
+        }
+        Some(sampling::rotation_about(angles, &self.pivot.map(f64::from)))
+    }
+
+    pub fn bounds(&self) -> sampling::Cube {
+        let b = self.bounds.map(f64::from);
+        sampling::Cube::new(b[0], b[1], b[2], b[3], b[4], b[5])
+    }
 }
 
 #[derive(Debug)]
@@ -34,6 +92,12 @@ pub struct SliceCache {
     pub cache: lru::LruCache<CachableSlicerParams, ndarray::Array2<f64>>,
 }
 
+impl Default for SliceCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SliceCache {
     pub fn new() -> Self {
         Self {
@@ -45,15 +109,33 @@ impl SliceCache {
         &mut self,
         volume: &brain_volume::BrainVolume,
         sample: CachableSlicerParams,
-    ) -> ndarray::ArrayView2<f64> {
+    ) -> ndarray::ArrayView2<'_, f64> {
         self.cache
-            .get_or_insert(sample, || {
-                volume.world_slice(
+            .get_or_insert(sample, || match (sample.projection, sample.space) {
+                (sampling::Projection::Slice, sampling::SliceSpace::World) => volume.world_slice(
+                    sample.axis,
+                    sample.depth.into(),
+                    sample.default_value.into(),
+                    sample.resolution,
+                    sample.bounds(),
+                    sample.transform().as_ref().map(|t| t.view()).as_ref(),
+                ),
+                (sampling::Projection::Slice, sampling::SliceSpace::Voxel) => volume.local_slice(
                     sample.axis,
                     sample.depth.into(),
                     sample.default_value.into(),
                     sample.resolution,
-                )
+                    sample.bounds(),
+                ),
+                (projection, space) => volume.projection(
+                    projection,
+                    space,
+                    sample.axis,
+                    sample.default_value.into(),
+                    sample.resolution,
+                    sample.bounds(),
+                    sample.transform().as_ref().map(|t| t.view()).as_ref(),
+                ),
 
                 /*let arr2d_norm: ndarray::Array2<u16> =
                         normalize_u16_f64(&arr2d, sample.intensity_range());
@@ -5,6 +5,10 @@ pub struct ColorBarWidget {
     pub color_mode: utils::colors::ColorMode,
     pub min: f64,
     pub max: f64,
+    /// Number and RGB color of NaN and infinite voxels, labelled next to the bar if any.
+    pub non_finite: Option<(usize, [u8; 3])>,
+    /// Display thresholds, the bar is left blank outside.
+    pub display_threshold: (Option<f64>, Option<f64>),
 }
 
 impl ColorBarWidget {
@@ -19,15 +23,58 @@ impl ColorBarWidget {
             color_mode,
             min,
             max,
+            non_finite: None,
+            display_threshold: (None, None),
         }
     }
+
+    pub fn display_threshold(mut self, display_threshold: (Option<f64>, Option<f64>)) -> Self {
+        self.display_threshold = display_threshold;
+        self
+    }
+
+    pub fn non_finite(mut self, count: usize, color: [u8; 3]) -> Self {
+        self.non_finite = (count > 0).then_some((count, color));
+        self
+    }
 }
 
 impl tui::widgets::Widget for ColorBarWidget {
     fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let area = match self.non_finite {
+            Some((count, color)) => {
+                let label = format!(" NaN/Inf: {} ", count);
+                let width = std::cmp::min(label.len() as u16, area.width / 2);
+                let style = match self.color_mode {
+                    utils::colors::ColorMode::Ascii => tui::style::Style::default(),
+                    mode => tui::style::Style::default()
+                        .bg(utils::colors::rgb2tui(color, mode))
+                        .fg(utils::colors::max_contrast(color)),
+                };
+                buf.set_stringn(area.right() - width, area.y, &label, width.into(), style);
+                tui::layout::Rect {
+                    width: area.width - width,
+                    ..area
+                }
+            }
+            None => area,
+        };
+
         let color_mapper = self.color_map.get();
         for i in 0..area.width {
-            let color = color_mapper.color(i as f64 / area.width as f64, self.color_mode);
+            let value = i as f64 / area.width as f64;
+            let intensity = self.min + value * (self.max - self.min);
+            if self.display_threshold.0.is_some_and(|lower| intensity < lower)
+                || self.display_threshold.1.is_some_and(|upper| intensity > upper)
+            {
+                continue;
+            }
+            if let utils::colors::ColorMode::Ascii = self.color_mode {
+                let shade = utils::colors::shade(color_mapper.luma(value), i as usize, 0);
+                buf.cell_mut((area.x + i, area.y)).map(|c| c.set_char(shade));
+                continue;
+            }
+            let color = color_mapper.color(value, self.color_mode);
             buf.cell_mut((area.x + i, area.y)).map(|c| c.set_bg(color));
         }
 
@@ -42,7 +89,7 @@ impl tui::widgets::Widget for ColorBarWidget {
             tui::style::Style::default(),
         );
         buf.set_stringn(
-            area.x + area.width as u16 - max_str.len() as u16,
+            area.x + area.width - max_str.len() as u16,
             area.y,
             &max_str,
             max_str.len(),
@@ -52,7 +99,7 @@ impl tui::widgets::Widget for ColorBarWidget {
         // write middle value
         let middle_str = format!("{:.2}", (self.min + self.max) / 2.);
         buf.set_stringn(
-            area.x + area.width as u16 / 2 - middle_str.len() as u16 / 2,
+            area.x + area.width / 2 - middle_str.len() as u16 / 2,
             area.y,
             &middle_str,
             middle_str.len(),
@@ -0,0 +1,87 @@
+pub struct FileTabsWidget<'a> {
+    pub file_paths: &'a [&'a str],
+    pub file_index: usize,
+}
+
+impl<'a> FileTabsWidget<'a> {
+    pub fn new(file_paths: &'a [&'a str], file_index: usize) -> FileTabsWidget<'a> {
+        Self {
+            file_paths,
+            file_index,
+        }
+    }
+}
+
+/// Shortens file paths to the part that distinguishes them from each other.
+///
+/// Leading directories shared by all paths and file extensions are dropped,
+/// e.g. `/data/sub-01/anat/T1w.nii.gz` and `/data/sub-02/anat/T1w.nii.gz`
+/// become `sub-01/anat/T1w` and `sub-02/anat/T1w`.
+pub fn short_file_labels(file_paths: &[&str]) -> Vec<String> {
+    let components: Vec<Vec<&str>> = file_paths
+        .iter()
+        .map(|p| p.split('/').filter(|c| !c.is_empty()).collect())
+        .collect();
+
+    let min_len = components.iter().map(|c| c.len()).min().unwrap_or(0);
+    let mut common = 0;
+    while common + 1 < min_len && components.iter().all(|c| c[common] == components[0][common]) {
+        common += 1;
+    }
+
+    components
+        .iter()
+        .map(|c| {
+            let label = c[common..].join("/");
+            match label.find(".nii") {
+                Some(idx) => label[..idx].to_string(),
+                None => label,
+            }
+        })
+        .collect()
+}
+
+impl<'a> tui::widgets::Widget for FileTabsWidget<'a> {
+    fn render(self, area: tui::prelude::Rect, buf: &mut tui::prelude::Buffer) {
+        let labels = short_file_labels(self.file_paths);
+
+        // draw counter from the right
+        let counter = format!("[{}/{}]", self.file_index + 1, self.file_paths.len());
+        if counter.len() >= area.width as usize {
+            return;
+        }
+        let counter_x = area.x + area.width - counter.len() as u16;
+        buf.set_string(
+            counter_x,
+            area.y,
+            &counter,
+            tui::style::Style::default().fg(tui::style::Color::DarkGray),
+        );
+        let tabs_width = (counter_x - area.x) as usize;
+
+        // scroll tabs so that the selected one is visible
+        let mut first = self.file_index;
+        let mut width = labels[first].len() + 1;
+        while first > 0 && width + labels[first - 1].len() < tabs_width {
+            first -= 1;
+            width += labels[first].len() + 1;
+        }
+
+        let mut x = area.x;
+        for (idx, label) in labels.iter().enumerate().skip(first) {
+            let remaining = tabs_width.saturating_sub((x - area.x) as usize);
+            if remaining == 0 {
+                break;
+            }
+            let style = if self.file_index == idx {
+                tui::style::Style::default()
+                    .bg(tui::style::Color::Cyan)
+                    .add_modifier(tui::style::Modifier::BOLD)
+            } else {
+                tui::style::Style::default()
+            };
+            buf.set_stringn(x, area.y, label, remaining, style);
+            x += std::cmp::min(label.len() + 1, remaining) as u16;
+        }
+    }
+}
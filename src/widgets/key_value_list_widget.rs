@@ -46,18 +46,23 @@ impl<'a> tui::widgets::Widget for KeyValueListWidget<'a> {
                     self.style_value,
                 );
             } else if i_offset < (area.height - 1) as usize {
+                let key_len = std::cmp::min(self.max_key_len, area.width as usize);
+                let value_len = std::cmp::min(
+                    self.max_value_len,
+                    (area.width as usize).saturating_sub(key_len + 1),
+                );
                 buf.set_stringn(
                     area.x,
                     area.y + i_offset as u16,
                     key,
-                    self.max_key_len,
+                    key_len,
                     self.style_key,
                 );
                 buf.set_stringn(
-                    area.x + self.max_key_len as u16 + 1,
+                    area.x + key_len as u16 + 1,
                     area.y + i_offset as u16,
                     val,
-                    self.max_value_len,
+                    value_len,
                     self.style_value,
                 );
             } else if i_offset == (area.height - 1) as usize {
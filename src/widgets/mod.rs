@@ -1,4 +1,6 @@
 pub mod key_value_list_widget;
 pub mod slice_widget;
 pub mod title_bar;
-pub mod color_bar;
\ No newline at end of file
+pub mod color_bar;
+pub mod file_tabs;
+pub mod table_widget;
@@ -1,25 +1,123 @@
 use noisy_float::prelude::*;
 use tui::{
     prelude::{Constraint, Direction, Layout},
-    widgets::{Block, BorderType, Borders},
+    widgets::{Block, BorderType, Borders, Widget},
 };
 
-use crate::utils;
+use crate::{utils, widgets};
 
 static COORDS: [&str; 3] = ["Superior", "Anterior", "Right"];
+static AXIS_NAMES: [&str; 3] = ["z", "y", "x"];
+static VOXEL_AXIS_NAMES: [&str; 3] = ["k", "j", "i"];
 static RAS_LABELS: [[char; 2]; 3] = [['I', 'S'], ['P', 'A'], ['L', 'R']];
 
+/// How slices are drawn into the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Renderer {
+    /// Two vertical pixels per cell using the `▄` character.
+    HalfBlock,
+    /// 2x2 pixels per cell using quadrant block characters.
+    Quadrant,
+    /// 2x3 pixels per cell using sextant block characters.
+    Sextant,
+    /// 2x4 pixels per cell using Braille patterns, suited for masks and contours.
+    Braille,
+    /// Full resolution images using a terminal graphics protocol.
+    Graphics { cell_width: u16, cell_height: u16 },
+}
+
+impl Renderer {
+    /// Number of pixels per terminal cell (horizontal, vertical).
+    pub fn cell_size(&self) -> (usize, usize) {
+        match self {
+            Renderer::HalfBlock => (1, 2),
+            Renderer::Quadrant => (2, 2),
+            Renderer::Sextant => (2, 3),
+            Renderer::Braille => (2, 4),
+            Renderer::Graphics {
+                cell_width,
+                cell_height,
+            } => (*cell_width as usize, *cell_height as usize),
+        }
+    }
+
+    /// Character showing the given pixels of a cell in the foreground color.
+    ///
+    /// Bit `row * 2 + column` of `pixels` is set for foreground pixels, rows counted from the top.
+    fn glyph(&self, pixels: u8) -> char {
+        const QUADRANTS: [char; 16] = [
+            ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+        ];
+        match self {
+            Renderer::Quadrant => QUADRANTS[pixels as usize & 0xf],
+            Renderer::Sextant => match pixels & 0x3f {
+                0 => ' ',
+                0b010101 => '▌',
+                0b101010 => '▐',
+                0b111111 => '█',
+                p => {
+                    // U+1FB00 onwards, skipping the patterns available as block elements
+                    let offset = p as u32 - 1 - (p > 0b010101) as u32 - (p > 0b101010) as u32;
+                    char::from_u32(0x1fb00 + offset).unwrap_or('▒')
+                }
+            },
+            Renderer::Braille => {
+                // dots 1-2-3 / 4-5-6 fill the left / right column of the first three rows, 7 and 8 the last row
+                const DOTS: [u32; 8] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+                let bits = (0..8)
+                    .filter(|i| pixels & (1 << i) != 0)
+                    .fold(0, |acc, i| acc | DOTS[i]);
+                char::from_u32(0x2800 + bits).unwrap_or(' ')
+            }
+            Renderer::HalfBlock | Renderer::Graphics { .. } => '▄',
+        }
+    }
+}
+
+impl clap::ValueEnum for Renderer {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Renderer::HalfBlock,
+            Renderer::Quadrant,
+            Renderer::Sextant,
+            Renderer::Braille,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        match self {
+            Renderer::HalfBlock => Some(clap::builder::PossibleValue::new("half-block")),
+            Renderer::Quadrant => Some(clap::builder::PossibleValue::new("quadrant")),
+            Renderer::Sextant => Some(clap::builder::PossibleValue::new("sextant")),
+            Renderer::Braille => Some(clap::builder::PossibleValue::new("braille")),
+            Renderer::Graphics { .. } => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct SliceParams {
     pub intensity_range: (f64, f64),
     pub position: Vec<f64>,
 
     pub color_mode: utils::colors::ColorMode,
     pub color_map: utils::colors::ColorMap,
+    pub renderer: Renderer,
+    /// Display patient left on screen right.
+    pub radiological: bool,
+    /// Coordinate system of `position`, slices are aligned to its axes.
+    pub frame: utils::sampling::SliceFrame,
+    /// Thin slices or projections through the volume.
+    pub projection: utils::sampling::Projection,
+    /// RGB color of NaN and infinite values.
+    pub nan_color: [u8; 3],
+    /// Values below the first or above the second threshold are transparent.
+    pub display_threshold: (Option<f64>, Option<f64>),
 }
 
 impl SliceParams {
     pub fn position_2d(&self, axis: usize) -> (f64, f64, f64) {
-        return position_2d(&self.position, axis);
+        position_2d(&self.position, axis)
     }
 }
 
@@ -40,7 +138,7 @@ where
         2 => 1,
         _ => panic!("Unsupported axis"),
     }];
-    return (index, x_index, y_index);
+    (index, x_index, y_index)
 }
 
 pub trait DoubleYImage {
@@ -59,9 +157,17 @@ pub trait DoubleYImage {
     /// Returns the color of the pixel at the given position.
     fn get(&self, x: usize, y: usize) -> tui::style::Color;
 
+    /// Returns whether the pixel at the given position is transparent,
+    /// i.e. the terminal background shows through.
+    fn is_transparent(&self, x: usize, y: usize) -> bool;
+
     /// Returns a contrasting color to the one returned by get().
     fn get_max_contrast(&self, x: usize, y: usize) -> tui::style::Color;
 
+    /// Returns the brightness (0 - 255) of the pixel at the given position,
+    /// for rendering without colors.
+    fn get_luma(&self, x: usize, y: usize) -> f64;
+
     /// Returns the average color of the double pixel at the given position ((x, y) + (x, y + 1)) / 2.
     fn get_double(&self, x: usize, y: usize) -> tui::style::Color;
 
@@ -114,6 +220,15 @@ impl tui::widgets::Widget for DoubleYImageRenderer<'_> {
                             let color_inverted = self.image.get_double_max_contrast(x, y);
                             c.set_bg(color).set_fg(color_inverted).set_char(overlay);
                         }
+                        // the foreground of the half block cannot be transparent
+                        None if self.image.is_transparent(x, y) => {
+                            if self.image.is_transparent(x, y + 1) {
+                                c.set_bg(tui::style::Color::Reset).set_char(' ');
+                            } else {
+                                let color_upper = self.image.get(x, y + 1);
+                                c.set_bg(tui::style::Color::Reset).set_fg(color_upper).set_char('▀');
+                            }
+                        }
                         None => {
                             let color_upper = self.image.get(x, y + 1);
                             let color_lower = self.image.get(x, y);
@@ -127,6 +242,338 @@ impl tui::widgets::Widget for DoubleYImageRenderer<'_> {
     }
 }
 
+/// Renders a DoubleYImage with several pixels per terminal cell.
+///
+/// The pixels of each cell are split into a brighter and a darker group,
+/// which are drawn as the foreground and background color of a block or
+/// Braille character (see [`Renderer`]). The area must be exactly as large
+/// as the image when measured in pixels.
+///
+/// Overlay characters are queried for the bottom left pixel of each cell
+/// and drawn on the average color of the cell.
+pub struct SubCellImageRenderer<'a> {
+    pub image: &'a dyn DoubleYImage,
+    pub renderer: Renderer,
+    pub color_mode: utils::colors::ColorMode,
+}
+
+impl<'a> SubCellImageRenderer<'a> {
+    pub fn new(
+        image: &'a dyn DoubleYImage,
+        renderer: Renderer,
+        color_mode: utils::colors::ColorMode,
+    ) -> SubCellImageRenderer<'a> {
+        Self {
+            image,
+            renderer,
+            color_mode,
+        }
+    }
+}
+
+fn average_rgb<'a>(colors: impl Iterator<Item = &'a [u8; 3]>) -> [u8; 3] {
+    let (sum, n) = colors.fold(([0usize; 3], 0), |(sum, n), c| {
+        ([sum[0] + c[0] as usize, sum[1] + c[1] as usize, sum[2] + c[2] as usize], n + 1)
+    });
+    sum.map(|c| (c / std::cmp::max(n, 1)) as u8)
+}
+
+fn luma(rgb: &[u8; 3]) -> f64 {
+    0.2126 * rgb[0] as f64 + 0.7152 * rgb[1] as f64 + 0.0722 * rgb[2] as f64
+}
+
+impl tui::widgets::Widget for SubCellImageRenderer<'_> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let (cell_width, cell_height) = self.renderer.cell_size();
+        assert!(
+            self.image.size().0 >= area.width as usize * cell_width
+                && self.image.size().1 >= area.height as usize * cell_height,
+            "Image size: {:?} < area size: {}x{}",
+            self.image.size(),
+            area.width as usize * cell_width,
+            area.height as usize * cell_height
+        );
+
+        let mut pixels = Vec::with_capacity(cell_width * cell_height);
+        for row in 0..(area.height as usize) {
+            for column in 0..(area.width as usize) {
+                let x0 = column * cell_width;
+                let y0 = (area.height as usize - row - 1) * cell_height; // y is flipped
+                let Some(c) = buf.cell_mut((area.left() + column as u16, area.top() + row as u16))
+                else {
+                    continue;
+                };
+
+                // pixels of the cell, starting at the top left, `None` if transparent
+                pixels.clear();
+                for dy in (0..cell_height).rev() {
+                    for dx in 0..cell_width {
+                        let (x, y) = (x0 + dx, y0 + dy);
+                        pixels.push(
+                            (!self.image.is_transparent(x, y))
+                                .then(|| utils::colors::tui2rgb(self.image.get(x, y))),
+                        );
+                    }
+                }
+
+                if let Some(overlay) = self.image.has_overlay(x0, y0) {
+                    if pixels.iter().all(Option::is_none) {
+                        c.set_bg(tui::style::Color::Reset)
+                            .set_fg(tui::style::Color::Reset)
+                            .set_char(overlay);
+                        continue;
+                    }
+                    let color = average_rgb(pixels.iter().flatten());
+                    let color_inverted = if luma(&color) < 128. {
+                        tui::style::Color::White
+                    } else {
+                        tui::style::Color::Black
+                    };
+                    c.set_bg(utils::colors::rgb2tui(color, self.color_mode))
+                        .set_fg(color_inverted)
+                        .set_char(overlay);
+                    continue;
+                }
+
+                // transparent pixels are drawn as the default background
+                if pixels.iter().any(Option::is_none) {
+                    let opaque = pixels
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, p)| p.is_some())
+                        .fold(0u8, |acc, (i, _)| acc | (1 << i));
+                    let color_fg = average_rgb(pixels.iter().flatten());
+                    c.set_bg(tui::style::Color::Reset)
+                        .set_fg(utils::colors::rgb2tui(color_fg, self.color_mode))
+                        .set_char(if opaque == 0 { ' ' } else { self.renderer.glyph(opaque) });
+                    continue;
+                }
+                let pixels: Vec<[u8; 3]> = pixels.iter().flatten().copied().collect();
+
+                let lumas: Vec<f64> = pixels.iter().map(luma).collect();
+                let threshold = (lumas.iter().copied().fold(f64::INFINITY, f64::min)
+                    + lumas.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+                    / 2.;
+                let foreground = lumas
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &l)| l > threshold)
+                    .fold(0u8, |acc, (i, _)| acc | (1 << i));
+
+                let color_fg = average_rgb(
+                    pixels
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| foreground & (1 << i) != 0)
+                        .map(|(_, p)| p),
+                );
+                let color_bg = average_rgb(
+                    pixels
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| foreground & (1 << i) == 0)
+                        .map(|(_, p)| p),
+                );
+                c.set_bg(utils::colors::rgb2tui(color_bg, self.color_mode))
+                    .set_fg(utils::colors::rgb2tui(color_fg, self.color_mode))
+                    .set_char(self.renderer.glyph(foreground));
+            }
+        }
+    }
+}
+
+/// Renders a DoubleYImage without colors, using ASCII characters of increasing density.
+///
+/// Each cell covers `cell_size` pixels (see [`Renderer::cell_size`]), whose average
+/// brightness is shaded with ordered dithering. Overlay characters are queried for
+/// the bottom left pixel of each cell.
+pub struct ShadingImageRenderer<'a> {
+    pub image: &'a dyn DoubleYImage,
+    pub cell_size: (usize, usize),
+}
+
+impl<'a> ShadingImageRenderer<'a> {
+    pub fn new(image: &'a dyn DoubleYImage, cell_size: (usize, usize)) -> ShadingImageRenderer<'a> {
+        Self { image, cell_size }
+    }
+}
+
+impl tui::widgets::Widget for ShadingImageRenderer<'_> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let (cell_width, cell_height) = self.cell_size;
+        for row in 0..(area.height as usize) {
+            for column in 0..(area.width as usize) {
+                let x0 = column * cell_width;
+                let y0 = (area.height as usize - row - 1) * cell_height; // y is flipped
+                let (bx, by) = (area.left() + column as u16, area.top() + row as u16);
+                let Some(c) = buf.cell_mut((bx, by)) else {
+                    continue;
+                };
+
+                let symbol = match self.image.has_overlay(x0, y0) {
+                    Some(overlay) => overlay,
+                    None => {
+                        let luma = (0..cell_height)
+                            .flat_map(|dy| (0..cell_width).map(move |dx| (x0 + dx, y0 + dy)))
+                            .map(|(x, y)| self.image.get_luma(x, y))
+                            .sum::<f64>()
+                            / (cell_width * cell_height) as f64;
+                        utils::colors::shade(luma, bx as usize, by as usize)
+                    }
+                };
+                c.reset();
+                c.set_char(symbol);
+            }
+        }
+    }
+}
+
+/// Renders a DoubleYImage at full resolution with a terminal graphics protocol.
+///
+/// The area must be exactly as large as the image when measured in pixels.
+/// The pixels are collected in `images` to be emitted by the terminal after
+/// the buffer is drawn; the covered cells are skipped when drawing the buffer.
+///
+/// Overlay characters are not supported; a crosshair can be drawn instead.
+pub struct GraphicsImageRenderer<'a> {
+    pub image: &'a dyn DoubleYImage,
+    pub images: &'a mut Vec<utils::graphics::GraphicsImage>,
+    /// Pixel column and row (counted from the bottom) of the crosshair lines.
+    pub crosshair: Option<(usize, usize)>,
+}
+
+impl<'a> GraphicsImageRenderer<'a> {
+    pub fn new(
+        image: &'a dyn DoubleYImage,
+        images: &'a mut Vec<utils::graphics::GraphicsImage>,
+    ) -> GraphicsImageRenderer<'a> {
+        Self {
+            image,
+            images,
+            crosshair: None,
+        }
+    }
+
+    pub fn crosshair(mut self, x: usize, y: usize) -> GraphicsImageRenderer<'a> {
+        self.crosshair = Some((x, y));
+        self
+    }
+}
+
+fn rgba(color: tui::style::Color) -> [u8; 4] {
+    let [r, g, b] = utils::colors::tui2rgb(color);
+    [r, g, b, 255]
+}
+
+impl tui::widgets::Widget for GraphicsImageRenderer<'_> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        let (width, height) = self.image.size();
+        if width == 0 || height == 0 || area.is_empty() {
+            return;
+        }
+
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for row in 0..height {
+            let y = height - row - 1; // y is flipped
+            for x in 0..width {
+                let crosshair = matches!(self.crosshair, Some((cx, cy)) if x == cx || y == cy);
+                let rgba = match (crosshair, self.image.is_transparent(x, y)) {
+                    // visible on dark and light backgrounds
+                    (true, true) => [128, 128, 128, 255],
+                    (false, true) => [0; 4],
+                    (true, false) => rgba(self.image.get_max_contrast(x, y)),
+                    (false, false) => rgba(self.image.get(x, y)),
+                };
+                pixels.extend_from_slice(&rgba);
+            }
+        }
+
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                if let Some(c) = buf.cell_mut((x, y)) {
+                    c.reset();
+                    c.set_skip(true);
+                }
+            }
+        }
+
+        self.images.push(utils::graphics::GraphicsImage {
+            area,
+            width,
+            height,
+            pixels,
+        });
+    }
+}
+
+/// A second image interleaved with the displayed one in a checkerboard pattern.
+pub struct CheckerboardPartner<'a> {
+    pub volume: &'a utils::brain_volume::BrainVolume,
+    pub image_cache: &'a mut utils::slice_cache::SliceCache,
+    pub slice: &'a SliceParams,
+}
+
+/// Position of a rendered slice on screen.
+#[derive(Debug, Clone, Copy)]
+pub struct SlicePlacement {
+    /// Cells covered by the slice image.
+    pub area: tui::layout::Rect,
+    /// Display axis of the slice.
+    pub axis: usize,
+    /// Coordinate of the slice along `axis`.
+    pub depth: f64,
+    /// Region that is displayed, in world or voxel coordinates.
+    pub bounds: utils::sampling::Cube,
+    /// Whether the horizontal axis runs from right to left (radiological convention).
+    pub mirrored: bool,
+}
+
+impl SlicePlacement {
+    /// Coordinates (in the space of `bounds`) at the center of a cell as (axis, value) pairs
+    /// of the horizontal and vertical screen axis, if the cell is part of the slice.
+    pub fn position(&self, column: u16, row: u16) -> Option<[(usize, f64); 2]> {
+        let area = self.area;
+        if column < area.left() || column >= area.right() || row < area.top() || row >= area.bottom() {
+            return None;
+        }
+        let (_, h_axis, v_axis) = position_2d(&[0, 1, 2], self.axis);
+        let (min, size) = (self.bounds.min(), self.bounds.size());
+        let h = ((column - area.left()) as f64 + 0.5) / area.width as f64;
+        let h = if self.mirrored { 1. - h } else { h };
+        let v = (row - area.top()) as f64 + 0.5;
+        Some([
+            (h_axis, min[h_axis] + h * size[h_axis]),
+            // rows are counted from the top
+            (v_axis, min[v_axis] + size[v_axis] - v / area.height as f64 * size[v_axis]),
+        ])
+    }
+}
+
+/// Everything rendered by slice widgets besides the buffer contents.
+#[derive(Debug, Default)]
+pub struct SliceOutput {
+    /// Pixel images when rendering with [`Renderer::Graphics`].
+    pub images: Vec<utils::graphics::GraphicsImage>,
+    pub placements: Vec<SlicePlacement>,
+}
+
+impl SliceOutput {
+    pub fn clear(&mut self) {
+        self.images.clear();
+        self.placements.clear();
+    }
+
+    /// Slice placement and coordinates below a cell, see [`SlicePlacement::position`].
+    pub fn position(&self, column: u16, row: u16) -> Option<(&SlicePlacement, [(usize, f64); 2])> {
+        self.placements
+            .iter()
+            .find_map(|p| p.position(column, row).map(|position| (p, position)))
+    }
+}
+
+/// Number of checkerboard tiles along the slice width.
+const CHECKERBOARD_TILES: usize = 8;
+
 pub struct SliceWidget<'a> {
     pub volume: &'a utils::brain_volume::BrainVolume,
     pub image_cache: &'a mut utils::slice_cache::SliceCache,
@@ -134,6 +581,13 @@ pub struct SliceWidget<'a> {
     pub slice: &'a SliceParams,
     pub axis: usize,
     pub block: Option<tui::widgets::Block<'a>>,
+    /// World space region that is displayed.
+    pub bounds: utils::sampling::Cube,
+    pub checkerboard: Option<CheckerboardPartner<'a>>,
+    /// Receives the pixel images and the placement of the slices.
+    pub output: Option<&'a mut SliceOutput>,
+    /// Whether the crosshair lines and orientation labels are drawn.
+    pub crosshair: bool,
 }
 
 impl<'a> SliceWidget<'a> {
@@ -149,6 +603,10 @@ impl<'a> SliceWidget<'a> {
             slice,
             axis,
             block: None,
+            bounds: volume.bounds(slice.frame.space),
+            checkerboard: None,
+            output: None,
+            crosshair: true,
         }
     }
 
@@ -156,6 +614,36 @@ impl<'a> SliceWidget<'a> {
         self.block = Some(block);
         self
     }
+
+    pub fn bounds(mut self, bounds: utils::sampling::Cube) -> SliceWidget<'a> {
+        self.bounds = bounds;
+        self
+    }
+
+    pub fn checkerboard(mut self, partner: CheckerboardPartner<'a>) -> SliceWidget<'a> {
+        self.checkerboard = Some(partner);
+        self
+    }
+
+    pub fn output(mut self, output: &'a mut SliceOutput) -> SliceWidget<'a> {
+        self.output = Some(output);
+        self
+    }
+
+    pub fn crosshair(mut self, crosshair: bool) -> SliceWidget<'a> {
+        self.crosshair = crosshair;
+        self
+    }
+}
+
+/// Start and size of the vertical and horizontal screen axis of a slice along `axis`.
+fn plane_extent(bounds: &utils::sampling::Cube, axis: usize) -> (f64, f64, f64, f64) {
+    match axis {
+        0 => (bounds.y0, bounds.yd, bounds.z0, bounds.zd),
+        1 => (bounds.x0, bounds.xd, bounds.z0, bounds.zd),
+        2 => (bounds.x0, bounds.xd, bounds.y0, bounds.yd),
+        _ => (0.0, 0.0, 0.0, 0.0),
+    }
 }
 
 fn fit_relative(src_x: f64, src_y: f64, dest_x: f64, dest_y: f64) -> (f64, f64) {
@@ -168,17 +656,63 @@ fn fit_relative(src_x: f64, src_y: f64, dest_x: f64, dest_y: f64) -> (f64, f64)
         dest_y as f32 / src_y as f32
     };
 
-    return (
+    (
         (src_x as f32 * resize_factor) as f64,
         (src_y as f32 * resize_factor) as f64,
-    );
+    )
 }
 
-struct RenderedSlice<T: Fn(usize, usize) -> Option<char>> {
-    pub image: ndarray::Array2<f64>,
+/// Slice image normalized to the intensity range of its [`SliceParams`].
+struct NormalizedImage {
+    pub values: ndarray::Array2<f64>,
     pub color_mapper: &'static utils::colors::ColorMapper,
+    /// Normalized display thresholds, values outside are transparent.
+    pub visible: (f64, f64),
+}
+
+impl NormalizedImage {
+    pub fn new(slice: &SliceParams, image: ndarray::Array2<f64>) -> NormalizedImage {
+        let (min, max) = slice.intensity_range;
+        // constant images would turn into NaN
+        let intensity_r = if max > min { max - min } else { 1. };
+        let normalize = |x: f64| (x - min) / intensity_r;
+        Self {
+            values: image.mapv(normalize),
+            color_mapper: slice.color_map.get(),
+            visible: (
+                slice.display_threshold.0.map_or(f64::NEG_INFINITY, normalize),
+                slice.display_threshold.1.map_or(f64::INFINITY, normalize),
+            ),
+        }
+    }
+
+    fn pixel(&self, value: f64) -> Pixel {
+        if !value.is_finite() {
+            Pixel::NonFinite
+        } else if value < self.visible.0 || value > self.visible.1 {
+            Pixel::Transparent
+        } else {
+            Pixel::Value(value, self.color_mapper)
+        }
+    }
+}
+
+/// How a normalized value is drawn.
+enum Pixel {
+    /// Outside of the display thresholds, the terminal background shows through.
+    Transparent,
+    /// NaN or infinite, drawn in [`SliceParams::nan_color`].
+    NonFinite,
+    Value(f64, &'static utils::colors::ColorMapper),
+}
+
+struct RenderedSlice<T: Fn(usize, usize) -> Option<char>> {
+    pub image: NormalizedImage,
     pub color_mode: utils::colors::ColorMode,
+    pub nan_color: [u8; 3],
     pub has_overlay_callback: T,
+    /// Image and tile size (in pixels) of a checkerboard partner.
+    pub checkerboard: Option<(NormalizedImage, usize)>,
 }
 
 impl<T: Fn(usize, usize) -> Option<char>> RenderedSlice<T> {
@@ -187,22 +721,53 @@ impl<T: Fn(usize, usize) -> Option<char>> RenderedSlice<T> {
         image: ndarray::Array2<f64>,
         has_overlay_callback: T,
     ) -> RenderedSlice<T> {
-        let intensity_r = slice.intensity_range.1 - slice.intensity_range.0;
-        let image = image.mapv(|x| (x - slice.intensity_range.0) / intensity_r);
-        let color_mapper = slice.color_map.get();
-
         Self {
-            image,
-            color_mapper,
+            image: NormalizedImage::new(slice, image),
             color_mode: slice.color_mode,
+            nan_color: slice.nan_color,
             has_overlay_callback,
+            checkerboard: None,
         }
     }
+
+    /// Interleaves tiles of `image` (which must have the same shape) in a checkerboard pattern.
+    ///
+    /// `tile_size` must be even so that tiles are aligned with double pixels.
+    pub fn checkerboard(
+        mut self,
+        slice: &SliceParams,
+        image: ndarray::Array2<f64>,
+        tile_size: usize,
+    ) -> RenderedSlice<T> {
+        assert_eq!(self.image.values.shape(), image.shape());
+        self.checkerboard = Some((NormalizedImage::new(slice, image), tile_size));
+        self
+    }
+
+    /// Returns the image shown at the given position.
+    fn layer(&self, x: usize, y: usize) -> &NormalizedImage {
+        match &self.checkerboard {
+            Some((image, tile_size)) if (x / tile_size + y / tile_size) % 2 == 1 => image,
+            _ => &self.image,
+        }
+    }
+
+    /// Returns how the pixel at the given position is drawn.
+    fn value(&self, x: usize, y: usize) -> Pixel {
+        let image = self.layer(x, y);
+        image.pixel(image.values[[x, y]])
+    }
+
+    /// Returns how the double pixel at the given position is drawn, from its average value.
+    fn value_double(&self, x: usize, y: usize) -> Pixel {
+        let image = self.layer(x, y);
+        image.pixel((image.values[[x, y]] + image.values[[x, y + 1]]) / 2.)
+    }
 }
 
 impl<T: Fn(usize, usize) -> Option<char>> DoubleYImage for RenderedSlice<T> {
     fn size(&self) -> (usize, usize) {
-        let shape = self.image.shape();
+        let shape = self.image.values.shape();
         (shape[0], shape[1])
     }
 
@@ -211,28 +776,47 @@ impl<T: Fn(usize, usize) -> Option<char>> DoubleYImage for RenderedSlice<T> {
     }
 
     fn get(&self, x: usize, y: usize) -> tui::style::Color {
-        let val = self.image[[x, y]];
-        self.color_mapper.color(val, self.color_mode)
+        match self.value(x, y) {
+            Pixel::Transparent => tui::style::Color::Reset,
+            Pixel::NonFinite => utils::colors::rgb2tui(self.nan_color, self.color_mode),
+            Pixel::Value(val, color_mapper) => color_mapper.color_dithered(val, self.color_mode, x, y),
+        }
+    }
+
+    fn is_transparent(&self, x: usize, y: usize) -> bool {
+        matches!(self.value(x, y), Pixel::Transparent)
     }
 
     fn get_max_contrast(&self, x: usize, y: usize) -> tui::style::Color {
-        let val = self.image[[x, y]];
-        self.color_mapper.color_max_contrast(val, self.color_mode)
+        match self.value(x, y) {
+            Pixel::Transparent => tui::style::Color::Reset,
+            Pixel::NonFinite => utils::colors::max_contrast(self.nan_color),
+            Pixel::Value(val, color_mapper) => color_mapper.color_max_contrast(val, self.color_mode),
+        }
+    }
+
+    fn get_luma(&self, x: usize, y: usize) -> f64 {
+        match self.value(x, y) {
+            Pixel::Transparent => 0.,
+            Pixel::NonFinite => luma(&self.nan_color),
+            Pixel::Value(val, color_mapper) => color_mapper.luma(val),
+        }
     }
 
     fn get_double(&self, x: usize, y: usize) -> tui::style::Color {
-        let val_lower = self.image[[x, y]];
-        let val_upper = self.image[[x, y + 1]];
-        let val_average = (val_lower + val_upper) / 2.;
-        self.color_mapper.color(val_average, self.color_mode)
+        match self.value_double(x, y) {
+            Pixel::Transparent => tui::style::Color::Reset,
+            Pixel::NonFinite => utils::colors::rgb2tui(self.nan_color, self.color_mode),
+            Pixel::Value(val, color_mapper) => color_mapper.color(val, self.color_mode),
+        }
     }
 
     fn get_double_max_contrast(&self, x: usize, y: usize) -> tui::style::Color {
-        let val_lower = self.image[[x, y]];
-        let val_upper = self.image[[x, y + 1]];
-        let val_average = (val_lower + val_upper) / 2.;
-        self.color_mapper
-            .color_max_contrast(val_average, self.color_mode)
+        match self.value_double(x, y) {
+            Pixel::Transparent => tui::style::Color::Reset,
+            Pixel::NonFinite => utils::colors::max_contrast(self.nan_color),
+            Pixel::Value(val, color_mapper) => color_mapper.color_max_contrast(val, self.color_mode),
+        }
     }
 }
 
@@ -247,45 +831,44 @@ impl tui::widgets::Widget for SliceWidget<'_> {
             None => area,
         };
 
-        let (world_v_min, world_v, world_h_min, world_h) = match self.axis {
-            0 => (
-                self.volume.world_bounds.y0,
-                self.volume.world_bounds.yd,
-                self.volume.world_bounds.z0,
-                self.volume.world_bounds.zd,
-            ),
-            1 => (
-                self.volume.world_bounds.x0,
-                self.volume.world_bounds.xd,
-                self.volume.world_bounds.z0,
-                self.volume.world_bounds.zd,
-            ),
-            2 => (
-                self.volume.world_bounds.x0,
-                self.volume.world_bounds.xd,
-                self.volume.world_bounds.y0,
-                self.volume.world_bounds.yd,
-            ),
-            _ => (0.0, 0.0, 0.0, 0.0),
-        };
+        let (world_v_min, world_v, world_h_min, world_h) = plane_extent(&self.bounds, self.axis);
 
-        let screen_width: usize = text_area.width as usize;
-        let screen_height: usize = text_area.height as usize * 2;
+        // pixel images need somewhere to go
+        let renderer = match (self.slice.renderer, &self.output) {
+            (Renderer::Graphics { .. }, None) => Renderer::HalfBlock,
+            (renderer, _) => renderer,
+        };
+        let (cell_width, cell_height) = renderer.cell_size();
+        let screen_width: usize = text_area.width as usize * cell_width;
+        let screen_height: usize = text_area.height as usize * cell_height;
 
         let (sampling_width, sampling_height) =
             fit_relative(world_h, world_v, screen_width as f64, screen_height as f64);
+        // whole cells only
+        let sampling_width = (sampling_width as usize / cell_width * cell_width) as f64;
+        let sampling_height = (sampling_height as usize / cell_height * cell_height) as f64;
+        if sampling_width == 0. || sampling_height == 0. {
+            return;
+        }
 
         let (index, x_index, y_index) = self.slice.position_2d(self.axis);
 
         let sampling_max = std::cmp::min(sampling_width as usize, sampling_height as usize);
-        let data_max = std::cmp::max(
+        // only the zoomed in part of the volume needs to be resolved
+        let (_, volume_v, _, volume_h) =
+            plane_extent(&self.volume.bounds(self.slice.frame.space), self.axis);
+        let visible = (world_h / volume_h).max(world_v / volume_v).min(1.);
+        let data_max = (std::cmp::max(
             self.volume.local_bounds.xd as usize,
             self.volume.local_bounds.yd as usize,
-        );
+        ) as f64
+            * visible)
+            .ceil() as usize;
         let downscale_factor = (data_max as f64 / sampling_max as f64).ceil() as usize;
-        let ssaa_factor = std::cmp::min(std::cmp::max(1, downscale_factor), 16);
+        let ssaa_factor = downscale_factor.clamp(1, 16);
 
         let sample = utils::slice_cache::CachableSlicerParams::new(
+            self.slice.frame.space,
             utils::sampling::SliceAxis::from_index(self.axis),
             n64(index),
             n64(0.),
@@ -293,11 +876,30 @@ impl tui::widgets::Widget for SliceWidget<'_> {
                 sampling_height as usize * ssaa_factor,
                 sampling_width as usize * ssaa_factor,
             ),
-        );
+            self.bounds,
+        )
+        .rotated(self.slice.frame.rotation, &self.slice.frame.pivot)
+        .projected(self.slice.projection);
+
+        // radiological convention: patient left (world x) on screen right
+        let voxel_space = self.slice.frame.space == utils::sampling::SliceSpace::Voxel;
+        let mirrored = self.slice.radiological && !voxel_space && self.axis != 2;
 
-        let img_arr = self.image_cache.get(&self.volume, sample).reversed_axes();
+        let img_arr = self.image_cache.get(self.volume, sample).reversed_axes();
 
-        let img_arr = utils::sampling::downsample_2d_array(&img_arr.view(), ssaa_factor);
+        let mut img_arr = utils::sampling::downsample_2d_array(&img_arr.view(), ssaa_factor);
+        if mirrored {
+            img_arr.invert_axis(ndarray::Axis(0));
+        }
+
+        let partner_img_arr = self.checkerboard.as_mut().map(|partner| {
+            let arr = partner.image_cache.get(partner.volume, sample).reversed_axes();
+            let mut arr = utils::sampling::downsample_2d_array(&arr.view(), ssaa_factor);
+            if mirrored {
+                arr.invert_axis(ndarray::Axis(0));
+            }
+            (partner.slice, arr)
+        });
 
         let img_arr_width = img_arr.shape()[0];
         let img_arr_height = img_arr.shape()[1];
@@ -315,46 +917,69 @@ impl tui::widgets::Widget for SliceWidget<'_> {
             screen_height
         );
 
-        let x_offset = (screen_width - img_arr_width) / 2;
-        let y_offset = (screen_height - img_arr_height) / 2;
+        let x_offset = (screen_width - img_arr_width) / cell_width / 2;
+        let y_offset = (screen_height - img_arr_height) / cell_height / 2;
 
         let image_render_area = tui::layout::Rect::new(
             text_area.left() + x_offset as u16,
-            text_area.top() + (y_offset / 2) as u16,
-            img_arr_width as u16,
-            (img_arr_height / 2) as u16,
+            text_area.top() + y_offset as u16,
+            (img_arr_width / cell_width) as u16,
+            (img_arr_height / cell_height) as u16,
         );
 
-        let y_index_rational =
-            ((y_index - world_v_min) / world_v * (img_arr_height as f64)) as usize;
-        let x_index_rational =
-            ((x_index - world_h_min) / world_h * (img_arr_width as f64)) as usize;
+        // the crosshair may be outside of a zoomed in view
+        let to_pixel = |value: f64, min: f64, size: f64, pixels: usize| {
+            let pixel = (value - min) / size * pixels as f64;
+            if self.crosshair && pixel >= 0. && pixel < pixels as f64 {
+                pixel as usize
+            } else {
+                usize::MAX
+            }
+        };
+        let x_index = if mirrored {
+            2. * world_h_min + world_h - x_index
+        } else {
+            x_index
+        };
+        let y_index_rational = to_pixel(y_index, world_v_min, world_v, img_arr_height);
+        let x_index_rational = to_pixel(x_index, world_h_min, world_h, img_arr_width);
 
         let symb = tui::symbols::line::ROUNDED;
         let symb_cross = symb.cross.chars().next().unwrap();
         let symb_vertical = symb.vertical.chars().next().unwrap();
         let symb_horizontal = symb.horizontal.chars().next().unwrap();
 
+        // array axes have no anatomical orientation
+        let (_, h_labels, v_labels) = position_2d(&RAS_LABELS, self.axis);
+        let (h_labels, v_labels) = if voxel_space {
+            ([symb_horizontal; 2], [symb_vertical; 2])
+        } else if mirrored {
+            ([h_labels[1], h_labels[0]], v_labels)
+        } else {
+            (h_labels, v_labels)
+        };
+
+        // overlays are placed per cell
         let img_slice = RenderedSlice::new(self.slice, img_arr, |x, y| {
-            let crossair_y = y / 2 * 2 == y_index_rational / 2 * 2;
-            let crossair_x = x == x_index_rational;
+            let crossair_y = y / cell_height == y_index_rational / cell_height;
+            let crossair_x = x / cell_width == x_index_rational / cell_width;
 
             match (crossair_x, crossair_y) {
                 (true, true) => Some(symb_cross),
                 (true, false) => {
-                    if y == 0 {
-                        Some(position_2d(&RAS_LABELS, self.axis).2[0])
-                    } else if y / 2 == img_arr_height / 2 - 1 {
-                        Some(position_2d(&RAS_LABELS, self.axis).2[1])
+                    if y / cell_height == 0 {
+                        Some(v_labels[0])
+                    } else if y / cell_height == img_arr_height / cell_height - 1 {
+                        Some(v_labels[1])
                     } else {
                         Some(symb_vertical)
                     }
                 }
                 (false, true) => {
-                    if x == 0 {
-                        Some(position_2d(&RAS_LABELS, self.axis).1[0])
-                    } else if x == img_arr_width - 1 {
-                        Some(position_2d(&RAS_LABELS, self.axis).1[1])
+                    if x / cell_width == 0 {
+                        Some(h_labels[0])
+                    } else if x / cell_width == img_arr_width / cell_width - 1 {
+                        Some(h_labels[1])
                     } else {
                         Some(symb_horizontal)
                     }
@@ -363,7 +988,75 @@ impl tui::widgets::Widget for SliceWidget<'_> {
             }
         });
 
-        DoubleYImageRenderer::new(&img_slice).render(image_render_area, buf);
+        let img_slice = match partner_img_arr {
+            Some((partner_slice, partner_arr)) => {
+                let tile_size = std::cmp::max(1, img_arr_width / CHECKERBOARD_TILES / 2) * 2;
+                img_slice.checkerboard(partner_slice, partner_arr, tile_size)
+            }
+            None => img_slice,
+        };
+
+        if let Some(output) = self.output.as_mut() {
+            output.placements.push(SlicePlacement {
+                area: image_render_area,
+                axis: self.axis,
+                depth: self.slice.position[self.axis],
+                bounds: self.bounds,
+                mirrored,
+            });
+        }
+
+        match (renderer, self.output) {
+            (Renderer::Graphics { .. }, Some(output)) => {
+                GraphicsImageRenderer::new(&img_slice, &mut output.images)
+                    .crosshair(x_index_rational, y_index_rational)
+                    .render(image_render_area, buf);
+            }
+            (renderer, _) if self.slice.color_mode == utils::colors::ColorMode::Ascii => {
+                ShadingImageRenderer::new(&img_slice, renderer.cell_size())
+                    .render(image_render_area, buf)
+            }
+            (Renderer::HalfBlock, _) | (Renderer::Graphics { .. }, None) => {
+                DoubleYImageRenderer::new(&img_slice).render(image_render_area, buf)
+            }
+            (renderer, _) => {
+                SubCellImageRenderer::new(&img_slice, renderer, self.slice.color_mode)
+                    .render(image_render_area, buf)
+            }
+        }
+    }
+}
+
+/// Arrangement of the slice panels of [`XyzWidget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XyzLayout {
+    /// Axial, coronal and sagittal side by side.
+    Row,
+    /// A single slice filling the whole area.
+    Single,
+    /// Three slices and an info panel in a 2x2 grid.
+    Grid,
+    /// Axial, coronal and sagittal stacked vertically, for tall terminals.
+    Column,
+    /// Evenly spaced slices along a single axis in a grid.
+    Mosaic,
+}
+
+impl XyzLayout {
+    pub fn next(&self) -> Self {
+        match self {
+            XyzLayout::Row => XyzLayout::Single,
+            XyzLayout::Single => XyzLayout::Grid,
+            XyzLayout::Grid => XyzLayout::Column,
+            XyzLayout::Column => XyzLayout::Mosaic,
+            XyzLayout::Mosaic => XyzLayout::Row,
+        }
+    }
+}
+
+impl std::fmt::Display for XyzLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
     }
 }
 
@@ -373,6 +1066,18 @@ pub struct XyzWidget<'a> {
 
     pub slice: &'a SliceParams,
     pub block: Option<tui::widgets::Block<'a>>,
+    /// World space region that is displayed.
+    pub bounds: utils::sampling::Cube,
+    pub checkerboard: Option<CheckerboardPartner<'a>>,
+    /// Receives the pixel images and the placement of the slices.
+    pub output: Option<&'a mut SliceOutput>,
+    pub layout: XyzLayout,
+    /// Axis shown by [`XyzLayout::Single`] and [`XyzLayout::Mosaic`].
+    pub single_axis: usize,
+    /// Shown in the fourth panel of [`XyzLayout::Grid`].
+    pub info: Option<&'a widgets::key_value_list_widget::KeyValueList>,
+    /// Number of slices shown by [`XyzLayout::Mosaic`].
+    pub mosaic_slices: usize,
 }
 
 impl<'a> XyzWidget<'a> {
@@ -386,6 +1091,13 @@ impl<'a> XyzWidget<'a> {
             image_cache,
             slice,
             block: None,
+            bounds: volume.bounds(slice.frame.space),
+            checkerboard: None,
+            output: None,
+            layout: XyzLayout::Row,
+            single_axis: 0,
+            info: None,
+            mosaic_slices: 12,
         }
     }
 
@@ -393,38 +1105,288 @@ impl<'a> XyzWidget<'a> {
         self.block = Some(block);
         self
     }
+
+    pub fn bounds(mut self, bounds: utils::sampling::Cube) -> XyzWidget<'a> {
+        self.bounds = bounds;
+        self
+    }
+
+    pub fn checkerboard(mut self, partner: CheckerboardPartner<'a>) -> XyzWidget<'a> {
+        self.checkerboard = Some(partner);
+        self
+    }
+
+    pub fn output(mut self, output: &'a mut SliceOutput) -> XyzWidget<'a> {
+        self.output = Some(output);
+        self
+    }
+
+    /// Arranges the panels, `single_axis` is shown by [`XyzLayout::Single`]
+    /// and [`XyzLayout::Mosaic`].
+    pub fn layout(mut self, layout: XyzLayout, single_axis: usize) -> XyzWidget<'a> {
+        self.layout = layout;
+        self.single_axis = single_axis;
+        self
+    }
+
+    pub fn info(
+        mut self,
+        info: &'a widgets::key_value_list_widget::KeyValueList,
+    ) -> XyzWidget<'a> {
+        self.info = Some(info);
+        self
+    }
+
+    /// Number of slices shown by [`XyzLayout::Mosaic`].
+    pub fn mosaic(mut self, slices: usize) -> XyzWidget<'a> {
+        self.mosaic_slices = slices;
+        self
+    }
+
+    /// Renders one slice panel with the checkerboard partner and output of this widget.
+    fn render_slice(
+        &mut self,
+        slice: &SliceParams,
+        axis: usize,
+        block: Block,
+        crosshair: bool,
+        area: tui::prelude::Rect,
+        buf: &mut tui::prelude::Buffer,
+    ) {
+        let mut slice_widget = SliceWidget::new(self.volume, self.image_cache, slice, axis)
+            .bounds(self.bounds)
+            .crosshair(crosshair);
+        if let Some(partner) = self.checkerboard.as_mut() {
+            slice_widget = slice_widget.checkerboard(CheckerboardPartner {
+                volume: partner.volume,
+                image_cache: partner.image_cache,
+                slice: partner.slice,
+            });
+        }
+        if let Some(output) = self.output.as_mut() {
+            slice_widget = slice_widget.output(output);
+        }
+        slice_widget.block(block).render(area, buf);
+    }
+
+    /// Renders evenly spaced slices along `single_axis` in a grid of tiles.
+    ///
+    /// The tile closest to the crosshair is highlighted.
+    fn render_mosaic(mut self, area: tui::prelude::Rect, buf: &mut tui::prelude::Buffer) {
+        let axis = self.single_axis;
+        let n = self.mosaic_slices.max(1);
+        let (_, world_v, _, world_h) = plane_extent(&self.bounds, axis);
+        let (columns, rows) = mosaic_grid(area, n, world_h, world_v, self.slice.renderer.cell_size());
+
+        let row_areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Ratio(1, rows as u32); rows])
+            .split(area);
+        let tile_areas: Vec<_> = row_areas
+            .iter()
+            .flat_map(|&row_area| {
+                Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Ratio(1, columns as u32); columns])
+                    .split(row_area)
+                    .to_vec()
+            })
+            .collect();
+
+        // slices at the borders of the volume are usually empty
+        let (min, size) = (
+            self.volume.bounds(self.slice.frame.space).min()[axis],
+            self.volume.bounds(self.slice.frame.space).size()[axis],
+        );
+        let depths: Vec<f64> = (0..n)
+            .map(|i| min + (i + 1) as f64 * size / (n + 1) as f64)
+            .collect();
+        let current = self.slice.position[axis];
+        let closest = (0..n)
+            .min_by(|&a, &b| (depths[a] - current).abs().total_cmp(&(depths[b] - current).abs()))
+            .unwrap_or(0);
+
+        for (i, (&depth, &tile_area)) in depths.iter().zip(tile_areas.iter()).enumerate() {
+            let mut slice = self.slice.clone();
+            slice.position[axis] = depth;
+            slice.projection = utils::sampling::Projection::Slice;
+            let border_style = if i == closest {
+                tui::style::Style::default().fg(tui::style::Color::Cyan)
+            } else {
+                tui::style::Style::default()
+            };
+            // short titles for small tiles
+            let title = match self.slice.frame.space {
+                utils::sampling::SliceSpace::World => format!(
+                    "{} = {:.1} {}",
+                    AXIS_NAMES[axis],
+                    depth,
+                    utils::brain_volume::xyz_units_str(&self.volume.header)
+                ),
+                utils::sampling::SliceSpace::Voxel => {
+                    format!("{} = {:.0}", VOXEL_AXIS_NAMES[axis], depth)
+                }
+            };
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_type(BorderType::Rounded)
+                .border_style(border_style);
+            self.render_slice(&slice, axis, block, false, tile_area, buf);
+        }
+    }
+}
+
+/// Number of columns and rows of a mosaic of `n` tiles that shows the slices largest.
+fn mosaic_grid(
+    area: tui::prelude::Rect,
+    n: usize,
+    world_h: f64,
+    world_v: f64,
+    cell_size: (usize, usize),
+) -> (usize, usize) {
+    let scale = |columns: usize| {
+        let rows = n.div_ceil(columns);
+        // without the tile borders
+        let width = (area.width as usize / columns).saturating_sub(2) * cell_size.0;
+        let height = (area.height as usize / rows).saturating_sub(2) * cell_size.1;
+        (width as f64 / world_h).min(height as f64 / world_v)
+    };
+    let columns = (1..=n)
+        .max_by(|&a, &b| scale(a).total_cmp(&scale(b)).then(b.cmp(&a)))
+        .unwrap_or(1);
+    (columns, n.div_ceil(columns))
 }
 
 impl<'a> tui::widgets::Widget for XyzWidget<'a> {
-    fn render(self, area: tui::prelude::Rect, buf: &mut tui::prelude::Buffer) {
-        let main_layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(
-                [
-                    Constraint::Ratio(1, 3),
-                    Constraint::Ratio(1, 3),
-                    Constraint::Ratio(1, 3),
-                ]
-                .as_ref(),
-            )
-            .split(area);
+    fn render(mut self, area: tui::prelude::Rect, buf: &mut tui::prelude::Buffer) {
+        let area = match self.block.take() {
+            Some(b) => {
+                let inner_area = b.inner(area);
+                b.render(area, buf);
+                inner_area
+            }
+            None => area,
+        };
+
+        let thirds = |direction| {
+            Layout::default()
+                .direction(direction)
+                .constraints([Constraint::Ratio(1, 3); 3].as_ref())
+                .split(area)
+        };
+        let halves = |direction, area| {
+            Layout::default()
+                .direction(direction)
+                .constraints([Constraint::Ratio(1, 2); 2].as_ref())
+                .split(area)
+        };
+
+        // (display axis, area) of the panels
+        let (panels, info_area) = match self.layout {
+            XyzLayout::Row => {
+                let columns = thirds(Direction::Horizontal);
+                (vec![(0, columns[0]), (1, columns[1]), (2, columns[2])], None)
+            }
+            XyzLayout::Column => {
+                let rows = thirds(Direction::Vertical);
+                (vec![(0, rows[0]), (1, rows[1]), (2, rows[2])], None)
+            }
+            XyzLayout::Single => (vec![(self.single_axis, area)], None),
+            XyzLayout::Mosaic => return self.render_mosaic(area, buf),
+            XyzLayout::Grid => {
+                let rows = halves(Direction::Vertical, area);
+                let top = halves(Direction::Horizontal, rows[0]);
+                let bottom = halves(Direction::Horizontal, rows[1]);
+                (vec![(0, top[0]), (1, top[1]), (2, bottom[0])], Some(bottom[1]))
+            }
+        };
+
+        for (display_axis, panel_area) in panels {
+            let position = self.slice.position[display_axis];
+            let title = match self.slice.frame.space {
+                _ if self.slice.projection != utils::sampling::Projection::Slice => format!(
+                    "{} along {}{}",
+                    self.slice.projection.label(),
+                    match self.slice.frame.space {
+                        utils::sampling::SliceSpace::World => AXIS_NAMES[display_axis],
+                        utils::sampling::SliceSpace::Voxel => VOXEL_AXIS_NAMES[display_axis],
+                    },
+                    if self.slice.frame.oblique() { " (oblique)" } else { "" }
+                ),
+                utils::sampling::SliceSpace::World => format!(
+                    "{} = {:.2} {}{}",
+                    COORDS[display_axis],
+                    position,
+                    utils::brain_volume::xyz_units_str(&self.volume.header),
+                    if self.slice.frame.oblique() { " (oblique)" } else { "" }
+                ),
+                utils::sampling::SliceSpace::Voxel => {
+                    format!("{} = {:.0}", VOXEL_AXIS_NAMES[display_axis], position)
+                }
+            };
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_type(BorderType::Rounded);
+            self.render_slice(self.slice, display_axis, block, true, panel_area, buf);
+        }
 
-        for i in 0..3 {
-            let display_axis = i;
-
-            SliceWidget::new(self.volume, self.image_cache, self.slice, display_axis)
-                .block(
-                    Block::default()
-                        .borders(Borders::ALL)
-                        .title(format!(
-                            "{} = {:.2} {}",
-                            COORDS[display_axis],
-                            self.slice.position[display_axis],
-                            utils::brain_volume::xyz_units_str(&self.volume.header)
-                        ))
-                        .border_type(BorderType::Rounded),
-                )
-                .render(main_layout[i], buf);
+        if let (Some(info), Some(info_area)) = (self.info, info_area) {
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title("Crosshair")
+                .border_type(BorderType::Rounded);
+            let inner_area = block.inner(info_area);
+            block.render(info_area, buf);
+            widgets::key_value_list_widget::KeyValueListWidget::new(info, 0).render(inner_area, buf);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Glyphs of the cells with a single foreground pixel, in bit order.
+    fn single_pixels(renderer: Renderer) -> Vec<char> {
+        let (width, height) = renderer.cell_size();
+        (0..width * height).map(|bit| renderer.glyph(1 << bit)).collect()
+    }
+
+    #[test]
+    fn quadrant_glyphs() {
+        assert_eq!(Renderer::Quadrant.glyph(0), ' ');
+        assert_eq!(Renderer::Quadrant.glyph(0b1111), '█');
+        assert_eq!(single_pixels(Renderer::Quadrant), ['▘', '▝', '▖', '▗']);
+        assert_eq!(Renderer::Quadrant.glyph(0b0101), '▌');
+        assert_eq!(Renderer::Quadrant.glyph(0b1100), '▄');
+    }
+
+    #[test]
+    fn sextant_glyphs() {
+        assert_eq!(Renderer::Sextant.glyph(0), ' ');
+        assert_eq!(Renderer::Sextant.glyph(0b111111), '█');
+        // BLOCK SEXTANT-1 to BLOCK SEXTANT-6
+        assert_eq!(
+            single_pixels(Renderer::Sextant),
+            ['\u{1fb00}', '\u{1fb01}', '\u{1fb03}', '\u{1fb07}', '\u{1fb0f}', '\u{1fb1e}']
+        );
+        // the columns are block elements, the patterns after them are shifted
+        assert_eq!(Renderer::Sextant.glyph(0b010101), '▌');
+        assert_eq!(Renderer::Sextant.glyph(0b101010), '▐');
+        assert_eq!(Renderer::Sextant.glyph(0b010110), '\u{1fb14}');
+        assert_eq!(Renderer::Sextant.glyph(0b111110), '\u{1fb3b}');
+    }
+
+    #[test]
+    fn braille_glyphs() {
+        assert_eq!(Renderer::Braille.glyph(0), '\u{2800}');
+        assert_eq!(Renderer::Braille.glyph(0xff), '\u{28ff}');
+        // dots 1, 4, 2, 5, 3, 6, 7, 8
+        assert_eq!(
+            single_pixels(Renderer::Braille),
+            ['⠁', '⠈', '⠂', '⠐', '⠄', '⠠', '⡀', '⢀']
+        );
+    }
+}
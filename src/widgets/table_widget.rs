@@ -0,0 +1,75 @@
+use tui::style::{Color, Modifier, Style};
+
+/// Rows of cells, all rows have the same number of cells as the header.
+pub type Table = Vec<Vec<String>>;
+
+pub struct TableWidget<'a> {
+    pub header: &'a [&'a str],
+    pub rows: &'a Table,
+    /// Highlighted row, kept in view.
+    pub selected: Option<usize>,
+    pub style_header: Style,
+    pub style_value: Style,
+    pub style_selected: Style,
+}
+
+impl<'a> TableWidget<'a> {
+    pub fn new(header: &'a [&'a str], rows: &'a Table, selected: Option<usize>) -> Self {
+        Self {
+            header,
+            rows,
+            selected,
+            style_header: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+            style_value: Style::default(),
+            style_selected: Style::default().add_modifier(Modifier::REVERSED),
+        }
+    }
+}
+
+impl<'a> tui::widgets::Widget for TableWidget<'a> {
+    fn render(self, area: tui::layout::Rect, buf: &mut tui::buffer::Buffer) {
+        if area.height == 0 {
+            return;
+        }
+
+        // right aligned columns, as wide as their widest cell
+        let widths: Vec<usize> = (0..self.header.len())
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .map(|row| row[column].len())
+                    .chain([self.header[column].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let render_row = |buf: &mut tui::buffer::Buffer, y: u16, cells: &[&str], style: Style| {
+            let line: Vec<String> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect();
+            buf.set_stringn(area.x, y, line.join("  "), area.width.into(), style);
+        };
+
+        render_row(buf, area.y, self.header, self.style_header);
+
+        // scroll so that the selected row is visible
+        let visible = (area.height - 1) as usize;
+        let offset = match self.selected {
+            Some(selected) if selected >= visible => selected + 1 - visible,
+            _ => 0,
+        };
+        for (i, row) in self.rows.iter().enumerate().skip(offset).take(visible) {
+            let style = if self.selected == Some(i) {
+                self.style_selected
+            } else {
+                self.style_value
+            };
+            let cells: Vec<&str> = row.iter().map(|cell| cell.as_str()).collect();
+            render_row(buf, area.y + 1 + (i - offset) as u16, &cells, style);
+        }
+    }
+}
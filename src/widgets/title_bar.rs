@@ -1,5 +1,8 @@
 pub struct TitleBarWidget<'a> {
     pub filename: &'a str,
+    pub description: Option<&'a str>,
+    /// Shown in red left of the modes, e.g. files that could not be loaded.
+    pub warning: Option<&'a str>,
     pub modes: &'a [&'a str],
     pub mode_index: usize,
 }
@@ -8,10 +11,22 @@ impl<'a> TitleBarWidget<'a> {
     pub fn new(title: &'a str, modes: &'a [&'a str], mode_index: usize) -> TitleBarWidget<'a> {
         Self {
             filename: title,
-            modes: modes,
-            mode_index: mode_index,
+            description: None,
+            warning: None,
+            modes,
+            mode_index,
         }
     }
+
+    pub fn description(mut self, description: Option<&'a str>) -> TitleBarWidget<'a> {
+        self.description = description;
+        self
+    }
+
+    pub fn warning(mut self, warning: Option<&'a str>) -> TitleBarWidget<'a> {
+        self.warning = warning;
+        self
+    }
 }
 
 fn path_extension_all(path: &std::path::Path) -> String {
@@ -22,13 +37,13 @@ fn path_extension_all(path: &std::path::Path) -> String {
         p = std::path::Path::new(p.file_stem().unwrap());
     }
     ext.pop();
-    return ext;
+    ext
 }
 
 impl<'a> tui::widgets::Widget for TitleBarWidget<'a> {
     fn render(self, area: tui::prelude::Rect, buf: &mut tui::prelude::Buffer) {
         // draw modes from the right
-        let mut x = area.x + area.width as u16;
+        let mut x = area.x + area.width;
         let mut modes_width = 0;
         for (idx, mode) in self.modes.iter().enumerate().rev() {
             x -= mode.len() as u16;
@@ -44,6 +59,18 @@ impl<'a> tui::widgets::Widget for TitleBarWidget<'a> {
             modes_width += mode.len() + 1;
         }
 
+        // draw the warning left of the modes
+        if let Some(warning) = self.warning {
+            if warning.len() + modes_width < area.width as usize {
+                x -= warning.len() as u16;
+                let style = tui::style::Style::default()
+                    .fg(tui::style::Color::Red)
+                    .add_modifier(tui::style::Modifier::BOLD);
+                buf.set_stringn(x, area.y, warning, warning.len(), style);
+                modes_width += warning.len() + 1;
+            }
+        }
+
         let filename = std::path::Path::new(self.filename);
         // filename directory
         let filename_dir = filename.parent().unwrap().to_str().unwrap().to_string() + "/";
@@ -58,7 +85,7 @@ impl<'a> tui::widgets::Widget for TitleBarWidget<'a> {
             .add_modifier(tui::style::Modifier::BOLD);
         let style_filename_ext = tui::style::Style::default().fg(tui::style::Color::DarkGray);
 
-        if filename_dir.len() + filename_stem.len() + filename_ext.len()
+        let pos_end = if filename_dir.len() + filename_stem.len() + filename_ext.len()
             < area.width as usize - modes_width
         {
             let pos_dir = area.x;
@@ -67,17 +94,29 @@ impl<'a> tui::widgets::Widget for TitleBarWidget<'a> {
 
             buf.set_string(pos_dir, area.y, filename_dir, style_filename_dir);
             buf.set_string(pos_stem, area.y, filename_stem, style_filename_stem);
-            buf.set_string(pos_ext, area.y, filename_ext, style_filename_ext);
+            buf.set_string(pos_ext, area.y, &filename_ext, style_filename_ext);
+            pos_ext + filename_ext.len() as u16
         } else if filename_stem.len() + filename_ext.len() < area.width as usize - modes_width {
             let pos_stem = area.x;
             let pos_ext = pos_stem + filename_stem.len() as u16;
 
             buf.set_string(pos_stem, area.y, filename_stem, style_filename_stem);
-            buf.set_string(pos_ext, area.y, filename_ext, style_filename_ext);
+            buf.set_string(pos_ext, area.y, &filename_ext, style_filename_ext);
+            pos_ext + filename_ext.len() as u16
         } else if filename_stem.len() < area.width as usize - modes_width {
             let pos_stem = area.x;
 
             buf.set_string(pos_stem, area.y, filename_stem, style_filename_stem);
+            pos_stem + filename_stem.len() as u16
+        } else {
+            return;
+        };
+
+        // draw description after the title
+        if let Some(description) = self.description {
+            let description = format!(" {}", description);
+            let remaining = (area.width as usize - modes_width).saturating_sub((pos_end - area.x) as usize + 1);
+            buf.set_stringn(pos_end, area.y, description, remaining, style_filename_dir);
         }
     }
 }